@@ -1,14 +1,193 @@
+use clap::builder::TypedValueParser as _;
+
 #[derive(clap::Parser)]
 #[command(about, author, version)]
 #[command(allow_missing_positional = true)]
 #[command(group = clap::ArgGroup::new("mode").multiple(false).required(true))]
 pub(crate) struct Args {
-    #[arg(default_value = "HEAD")]
-    pub(crate) rev: String,
+    /// Revision to annotate the file at
+    ///
+    /// Defaults to `HEAD`, or to the working tree when the file has uncommitted changes.
+    pub(crate) rev: Option<String>,
 
     #[arg(required = true, group = "mode")]
     pub(crate) file: Option<std::path::PathBuf>,
 
+    /// Annotate each line of `OLD` with the last commit it was present in on the way to `NEW`
+    ///
+    /// `NEW` defaults to `HEAD`.  Useful for finding when a line was removed.
+    #[arg(long, value_name = "OLD..NEW", conflicts_with = "rev")]
+    pub(crate) reverse: Option<String>,
+
+    /// Find the file at `REV` by following renames since then
+    #[arg(long, requires = "rev")]
+    pub(crate) follow: bool,
+
+    /// Also show when each line was next changed or removed on the way to `TIP`
+    ///
+    /// `TIP` defaults to `HEAD`.  Useful when annotating an old `REV`, like a release tag.
+    #[arg(
+        long,
+        value_name = "TIP",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "HEAD",
+        requires = "rev",
+        conflicts_with_all = ["history", "pickaxe_search"]
+    )]
+    pub(crate) next: Option<String>,
+
+    /// Annotate the version of the file in the index
+    #[arg(long, conflicts_with_all = ["rev", "reverse"])]
+    pub(crate) cached: bool,
+
+    /// Annotate only the given line range; may be given multiple times
+    ///
+    /// `RANGE` is `<start>,<end>` or `:<funcname>`.  `<start>` and `<end>` may be a line number
+    /// or `/<regex>/`, with `<end>` also accepting `+<offset>` or `-<offset>`.
+    #[arg(short = 'L', value_name = "RANGE")]
+    pub(crate) line_range: Vec<crate::line_range::LineRange>,
+
+    /// List every commit that changed the lines selected with `-L`, like `git log -L`
+    ///
+    /// Follows the lines back through renames and as they move around the file, starting from
+    /// `REV`.
+    #[arg(long, conflicts_with_all = ["reverse", "cached"])]
+    pub(crate) history: bool,
+
+    /// List the commits that changed how many times `STRING` appears in the file
+    ///
+    /// Like `git log -S`, this finds when something was added or removed, even if a line
+    /// mentioning it has since changed.
+    #[arg(
+        short = 'S',
+        value_name = "STRING",
+        group = "pickaxe_search",
+        conflicts_with_all = ["reverse", "cached", "history"]
+    )]
+    pub(crate) pickaxe: Option<String>,
+
+    /// Like `-S` but `REGEX` is a regular expression
+    #[arg(
+        short = 'G',
+        value_name = "REGEX",
+        group = "pickaxe_search",
+        conflicts_with_all = ["reverse", "cached", "history"]
+    )]
+    pub(crate) pickaxe_regex: Option<String>,
+
+    /// Rather than listing the commits found by `-S` or `-G`, blame the file just before the
+    /// newest one
+    #[arg(long, requires = "pickaxe_search")]
+    pub(crate) jump: bool,
+
+    /// Attribute each word or syntax token, rather than each line, to the commit that last
+    /// changed it
+    ///
+    /// Parts of a line from commits other than the line's are tinted, with a legend after the
+    /// blame, and `--format json` lists them as `spans`.
+    #[arg(long, value_enum, default_value_t, conflicts_with = "reverse")]
+    pub(crate) granularity: crate::granularity::Granularity,
+
+    /// Highlight the file as `LANGUAGE`, a name or extension from `--list-languages`
+    ///
+    /// Otherwise, the language comes from the `dive-language` attribute, `dive.syntaxMap`
+    /// entries like `*.j2:Jinja2`, the `linguist-language` attribute, and then the file's name or
+    /// first line.
+    #[arg(long, value_name = "LANGUAGE")]
+    pub(crate) language: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) format: crate::format::Format,
+
+    /// Shorthand for `--format porcelain`
+    #[arg(short = 'p', long, conflicts_with_all = ["format", "line_porcelain", "incremental"])]
+    pub(crate) porcelain: bool,
+
+    /// Shorthand for `--format line-porcelain`
+    #[arg(long, conflicts_with_all = ["format", "incremental"])]
+    pub(crate) line_porcelain: bool,
+
+    /// Shorthand for `--format incremental`
+    #[arg(long, conflicts_with_all = ["format"])]
+    pub(crate) incremental: bool,
+
+    /// Only follow the first parent of merges, attributing lines to the merge (default)
+    #[arg(long, overrides_with = "no_first_parent")]
+    first_parent: bool,
+
+    /// Follow all parents of merges, attributing lines to the commits on merged branches
+    #[arg(long, overrides_with = "first_parent")]
+    no_first_parent: bool,
+
+    /// Ignore whitespace changes when assigning blame (default)
+    #[arg(short = 'w', long, overrides_with = "no_ignore_whitespace")]
+    ignore_whitespace: bool,
+
+    /// Treat whitespace changes like any other
+    #[arg(long, overrides_with = "ignore_whitespace")]
+    no_ignore_whitespace: bool,
+
+    /// Detect lines moved within the file (default)
+    #[arg(short = 'M', long, overrides_with = "no_moves")]
+    moves: bool,
+
+    /// Don't detect lines moved within the file
+    #[arg(long, overrides_with = "moves")]
+    no_moves: bool,
+
+    /// Detect lines copied from other files, like `git blame -C` (default: 3)
+    ///
+    /// Repeat to look further: once for files changed in the same commit, twice to also include
+    /// files created in that commit, and three times for any commit.  Unlike `git blame`, `-C` is
+    /// for changing directories.
+    #[arg(long, action = clap::ArgAction::Count, overrides_with = "no_copies")]
+    copies: u8,
+
+    /// Don't detect lines copied from other files
+    #[arg(long, overrides_with = "copies")]
+    no_copies: bool,
+
+    /// Ignore changes made by `REV` when assigning blame
+    ///
+    /// Lines changed by `REV` are attributed to the commit that last changed the line before it.
+    #[arg(long, value_name = "REV", conflicts_with = "reverse")]
+    pub(crate) ignore_rev: Vec<String>,
+
+    /// Ignore revisions listed in `FILE`, in the format of `blame.ignoreRevsFile`
+    ///
+    /// An empty `FILE` clears any previously listed files, including from `blame.ignoreRevsFile`.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "reverse",
+        value_parser = clap::builder::OsStringValueParser::new().map(std::path::PathBuf::from)
+    )]
+    pub(crate) ignore_revs_file: Vec<std::path::PathBuf>,
+
+    /// Browse the blame interactively, jumping back through history
+    ///
+    /// `Enter` blames the parent of the commit for the line under the cursor, `h` / `l` go back
+    /// and forward through the blames visited, and `q` quits.  Falls back to the pager when not
+    /// connected to a terminal.
+    #[arg(short = 'i', long)]
+    pub(crate) interactive: bool,
+
+    /// Show the commit that last changed a line, with its message and diff
+    ///
+    /// The line is looked up in the file at `HEAD`, or in the working tree when the file has
+    /// uncommitted changes, or in the index with `--cached`.
+    #[arg(long, value_name = "FILE:LINE", group = "mode")]
+    pub(crate) explain: Option<crate::explain::Location>,
+
+    /// Step through every change to `FILE` on the first-parent history of `HEAD`
+    ///
+    /// Each change is shown as a diff, labelled with the commit it was made in.  With
+    /// `--interactive`, `h` / `l` move to older and newer changes.
+    #[arg(long, value_name = "FILE", group = "mode", conflicts_with = "rev")]
+    pub(crate) timelapse: Option<std::path::PathBuf>,
+
     #[arg(long, value_name = "PATH", group = "mode")]
     pub(crate) dump_config: Option<std::path::PathBuf>,
 
@@ -20,6 +199,12 @@ pub(crate) struct Args {
     #[arg(long, group = "mode")]
     pub(crate) list_themes: bool,
 
+    /// Compile custom syntaxes and themes ahead of time, remove them, or show what's cached
+    ///
+    /// Once built, the cache is used until a syntax or theme file changes.
+    #[arg(long, value_name = "ACTION", value_enum, group = "mode")]
+    pub(crate) cache: Option<crate::assets::CacheAction>,
+
     /// Display acknowledgements
     #[arg(long, hide_short_help = true, group = "mode")]
     pub(crate) acknowledgements: bool,
@@ -49,6 +234,51 @@ pub(crate) struct Args {
     pub(crate) verbose: clap_verbosity_flag::Verbosity<clap_verbosity_flag::InfoLevel>,
 }
 
+impl Args {
+    pub(crate) fn first_parent(&self) -> Option<bool> {
+        resolve_bool_arg(self.first_parent, self.no_first_parent)
+    }
+
+    pub(crate) fn ignore_whitespace(&self) -> Option<bool> {
+        resolve_bool_arg(self.ignore_whitespace, self.no_ignore_whitespace)
+    }
+
+    pub(crate) fn moves(&self) -> Option<bool> {
+        resolve_bool_arg(self.moves, self.no_moves)
+    }
+
+    pub(crate) fn copies(&self) -> Option<u8> {
+        if self.no_copies {
+            Some(0)
+        } else if 0 < self.copies {
+            Some(self.copies)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn format(&self) -> crate::format::Format {
+        if self.porcelain {
+            crate::format::Format::Porcelain
+        } else if self.line_porcelain {
+            crate::format::Format::LinePorcelain
+        } else if self.incremental {
+            crate::format::Format::Incremental
+        } else {
+            self.format
+        }
+    }
+}
+
+fn resolve_bool_arg(yes: bool, no: bool) -> Option<bool> {
+    match (yes, no) {
+        (true, false) => Some(true),
+        (false, true) => Some(false),
+        (false, false) => None,
+        (_, _) => unreachable!("clap should make them conflict"),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
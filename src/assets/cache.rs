@@ -0,0 +1,199 @@
+//! Custom syntaxes and themes, compiled ahead of time with `--cache build`
+
+use proc_exit::WithCodeResultExt;
+
+use super::{COMPRESS_THEMES, LazyThemeSet, custom_files, syntaxes_dir, themes_dir};
+use super::{Result, asset_from_contents, asset_to_contents};
+use crate::git2_config::Config;
+
+/// What to do with the cache, from `--cache`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub(crate) enum CacheAction {
+    /// Compile custom syntaxes and themes
+    Build,
+    /// Remove everything cached, including from other versions of git-dive
+    Clear,
+    /// Show where the cache is and whether it's up to date
+    Info,
+}
+
+pub(crate) fn cache(action: CacheAction, config: &Config) -> proc_exit::ExitResult {
+    use std::io::Write as _;
+
+    let dir = cache_dir()
+        .ok_or_else(|| anyhow::format_err!("could not find a cache directory, set `HOME`"))
+        .with_code(proc_exit::Code::FAILURE)?;
+    let syntaxes = syntaxes_dir(config)
+        .map(|dir| custom_files(&dir, "sublime-syntax"))
+        .unwrap_or_default();
+    let themes = themes_dir(config)
+        .map(|dir| custom_files(&dir, "tmTheme"))
+        .unwrap_or_default();
+
+    let mut stdout = anstream::stdout();
+    match action {
+        CacheAction::Build => {
+            build(&dir, &syntaxes, &themes).with_code(proc_exit::Code::FAILURE)?;
+            let _ = writeln!(
+                stdout,
+                "Cached {} syntaxes and {} themes in {}",
+                syntaxes.len(),
+                themes.len(),
+                dir.display()
+            );
+        }
+        CacheAction::Clear => {
+            let root = dir
+                .parent()
+                .expect("versioned under the git-dive directory");
+            if root.exists() {
+                std::fs::remove_dir_all(root).with_code(proc_exit::Code::FAILURE)?;
+                let _ = writeln!(stdout, "Removed {}", root.display());
+            } else {
+                let _ = writeln!(stdout, "Nothing cached in {}", root.display());
+            }
+        }
+        CacheAction::Info => {
+            let _ = writeln!(stdout, "Cache: {}", dir.display());
+            let _ = writeln!(
+                stdout,
+                "Syntaxes: {}",
+                describe(syntaxes_dir(config), &syntaxes, load_syntaxes)
+            );
+            let _ = writeln!(
+                stdout,
+                "Themes: {}",
+                describe(themes_dir(config), &themes, load_themes)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Custom syntaxes, merged into the built-in ones, if they're cached and up to date
+pub(super) fn load_syntaxes(paths: &[std::path::PathBuf]) -> Option<syntect::parsing::SyntaxSet> {
+    load(SYNTAXES_FILE, paths, COMPRESS_SYNTAXES)
+}
+
+/// Custom themes, if they're cached and up to date
+pub(super) fn load_themes(paths: &[std::path::PathBuf]) -> Option<LazyThemeSet> {
+    load(THEMES_FILE, paths, COMPRESS_THEMES)
+}
+
+fn build(
+    dir: &std::path::Path,
+    syntaxes: &[std::path::PathBuf],
+    themes: &[std::path::PathBuf],
+) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let syntax_set = super::compile_syntaxes(syntaxes);
+    write(
+        &dir.join(SYNTAXES_FILE),
+        syntaxes,
+        syntax_set,
+        COMPRESS_SYNTAXES,
+    )?;
+    let theme_set = LazyThemeSet::try_from(super::compile_themes(themes))?;
+    write(&dir.join(THEMES_FILE), themes, theme_set, COMPRESS_THEMES)?;
+    Ok(())
+}
+
+fn write<T: serde::Serialize>(
+    path: &std::path::Path,
+    paths: &[std::path::PathBuf],
+    asset: T,
+    compressed: bool,
+) -> Result<()> {
+    let cached = Cached {
+        sources: sources(paths),
+        asset,
+    };
+    let contents = asset_to_contents(&cached, compressed)?;
+    // Don't leave a partially written cache for other processes to find
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn load<T: serde::de::DeserializeOwned>(
+    file_name: &str,
+    paths: &[std::path::PathBuf],
+    compressed: bool,
+) -> Option<T> {
+    let path = cache_dir()?.join(file_name);
+    let contents = std::fs::read(&path).ok()?;
+    let cached = match asset_from_contents::<Cached<T>>(&contents, file_name, compressed) {
+        Ok(cached) => cached,
+        Err(err) => {
+            log::debug!("ignoring {}: {err}", path.display());
+            return None;
+        }
+    };
+    if cached.sources != sources(paths) {
+        log::debug!("ignoring {}: out of date", path.display());
+        return None;
+    }
+    Some(cached.asset)
+}
+
+/// Summarize the state of one kind of asset for `--cache info`
+fn describe<T>(
+    dir: Option<std::path::PathBuf>,
+    paths: &[std::path::PathBuf],
+    load: impl Fn(&[std::path::PathBuf]) -> Option<T>,
+) -> String {
+    let Some(dir) = dir else {
+        return "no directory to load from".to_owned();
+    };
+    let state = if paths.is_empty() {
+        "nothing to cache"
+    } else if load(paths).is_some() {
+        "cached"
+    } else {
+        "not cached, run `git dive --cache build`"
+    };
+    format!("{} in {} ({state})", paths.len(), dir.display())
+}
+
+/// Where compiled assets go, like `~/.cache/git-dive/0.1.6`
+///
+/// Each version gets its own directory since the serialized format can change between them.
+fn cache_dir() -> Option<std::path::PathBuf> {
+    super::default_dir("XDG_CACHE_HOME", ".cache", env!("CARGO_PKG_VERSION"))
+}
+
+/// An asset along with the files it was compiled from
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Cached<T> {
+    sources: Vec<Source>,
+    asset: T,
+}
+
+/// Enough about a file to tell when it has changed
+#[derive(PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+struct Source {
+    path: std::path::PathBuf,
+    len: u64,
+    modified: Option<std::time::SystemTime>,
+}
+
+fn sources(paths: &[std::path::PathBuf]) -> Vec<Source> {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok();
+            Source {
+                path: path.clone(),
+                len: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                modified: metadata.and_then(|m| m.modified().ok()),
+            }
+        })
+        .collect()
+}
+
+const SYNTAXES_FILE: &str = "syntaxes.bin";
+const THEMES_FILE: &str = "themes.bin";
+
+/// Compiled syntaxes compress well and are read all at once
+const COMPRESS_SYNTAXES: bool = true;
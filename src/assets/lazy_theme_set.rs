@@ -1,4 +1,4 @@
-use super::{COMPRESS_LAZY_THEMES, Error, Result, asset_from_contents};
+use super::{COMPRESS_LAZY_THEMES, Error, Result, asset_from_contents, asset_to_contents};
 
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
@@ -39,6 +39,20 @@ impl LazyThemeSet {
         })
     }
 
+    /// Add an already loaded theme, replacing any by the same name
+    pub(crate) fn insert(&mut self, name: String, theme: Theme) {
+        let lazy_theme = LazyTheme {
+            serialized: Vec::new(),
+            deserialized: OnceCell::with_value(theme),
+        };
+        self.themes.insert(name, lazy_theme);
+    }
+
+    /// Add every theme from `other`, replacing any by the same name
+    pub(crate) fn extend(&mut self, other: LazyThemeSet) {
+        self.themes.extend(other.themes);
+    }
+
     /// Returns the name of all themes.
     pub(crate) fn themes(&self) -> impl Iterator<Item = &str> {
         self.themes.keys().map(|name| name.as_ref())
@@ -66,9 +80,36 @@ impl TryFrom<LazyThemeSet> for ThemeSet {
         let mut theme_set = ThemeSet::default();
 
         for (name, lazy_theme) in lazy_theme_set.themes {
-            theme_set.themes.insert(name, lazy_theme.deserialize()?);
+            let theme = match lazy_theme.deserialized.into_inner() {
+                Some(theme) => theme,
+                None => LazyTheme {
+                    serialized: lazy_theme.serialized,
+                    deserialized: OnceCell::new(),
+                }
+                .deserialize()?,
+            };
+            theme_set.themes.insert(name, theme);
         }
 
         Ok(theme_set)
     }
 }
+
+impl TryFrom<ThemeSet> for LazyThemeSet {
+    type Error = Error;
+
+    /// Serialize each theme so it can be written out and lazily loaded again
+    fn try_from(theme_set: ThemeSet) -> Result<Self> {
+        let mut lazy_theme_set = LazyThemeSet::default();
+
+        for (name, theme) in theme_set.themes {
+            let lazy_theme = LazyTheme {
+                serialized: asset_to_contents(&theme, COMPRESS_LAZY_THEMES)?,
+                deserialized: OnceCell::new(),
+            };
+            lazy_theme_set.themes.insert(name, lazy_theme);
+        }
+
+        Ok(lazy_theme_set)
+    }
+}
@@ -1,18 +1,129 @@
+mod cache;
 mod lazy_theme_set;
 
 use anyhow::Error;
 use anyhow::Result;
 
+use crate::git2_config::Config;
+use crate::git2_config::RawField;
+
+pub(crate) use cache::CacheAction;
+pub(crate) use cache::cache;
 pub(crate) use lazy_theme_set::LazyThemeSet;
 
-pub(crate) fn load_themes() -> LazyThemeSet {
-    get_integrated_themeset()
+/// The built-in themes, along with any `.tmTheme` files in `dive.themesDir`
+pub(crate) fn load_themes(config: &Config) -> LazyThemeSet {
+    let mut theme_set = get_integrated_themeset();
+    let paths = themes_dir(config)
+        .map(|dir| custom_files(&dir, "tmTheme"))
+        .unwrap_or_default();
+    if paths.is_empty() {
+        return theme_set;
+    }
+    match cache::load_themes(&paths) {
+        Some(custom) => theme_set.extend(custom),
+        None => {
+            for (name, theme) in compile_themes(&paths).themes {
+                theme_set.insert(name, theme);
+            }
+        }
+    }
+    theme_set
+}
+
+/// The built-in syntaxes, along with any `.sublime-syntax` files in `dive.syntaxesDir`
+pub(crate) fn load_syntaxes(config: &Config) -> syntect::parsing::SyntaxSet {
+    let paths = syntaxes_dir(config)
+        .map(|dir| custom_files(&dir, "sublime-syntax"))
+        .unwrap_or_default();
+    if paths.is_empty() {
+        return integrated_syntaxes();
+    }
+    cache::load_syntaxes(&paths).unwrap_or_else(|| compile_syntaxes(&paths))
+}
+
+/// Load each theme, skipping those that can't be
+fn compile_themes(paths: &[std::path::PathBuf]) -> syntect::highlighting::ThemeSet {
+    let mut theme_set = syntect::highlighting::ThemeSet::default();
+    for path in paths {
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        match syntect::highlighting::ThemeSet::get_theme(path) {
+            Ok(theme) => {
+                theme_set.themes.insert(name.to_owned(), theme);
+            }
+            Err(err) => log::warn!("skipping theme {}: {err}", path.display()),
+        }
+    }
+    theme_set
+}
+
+/// Add each syntax to the built-in ones, skipping those that can't be loaded
+fn compile_syntaxes(paths: &[std::path::PathBuf]) -> syntect::parsing::SyntaxSet {
+    let mut builder = integrated_syntaxes().into_builder();
+    for path in paths {
+        let definition = std::fs::read_to_string(path)
+            .map_err(Error::from)
+            .and_then(|contents| {
+                let fallback_name = path.file_stem().and_then(|s| s.to_str());
+                syntect::parsing::SyntaxDefinition::load_from_str(&contents, true, fallback_name)
+                    .map_err(Error::from)
+            });
+        match definition {
+            Ok(definition) => builder.add(definition),
+            Err(err) => log::warn!("skipping syntax {}: {err}", path.display()),
+        }
+    }
+    builder.build()
+}
+
+fn syntaxes_dir(config: &Config) -> Option<std::path::PathBuf> {
+    config
+        .get(&SYNTAXES_DIR)
+        .or_else(|| default_dir("XDG_CONFIG_HOME", ".config", "syntaxes"))
 }
 
-pub(crate) fn load_syntaxes() -> syntect::parsing::SyntaxSet {
+fn themes_dir(config: &Config) -> Option<std::path::PathBuf> {
+    config
+        .get(&THEMES_DIR)
+        .or_else(|| default_dir("XDG_CONFIG_HOME", ".config", "themes"))
+}
+
+/// The syntaxes built into the binary, ignoring any from `dive.syntaxesDir`
+pub(crate) fn integrated_syntaxes() -> syntect::parsing::SyntaxSet {
     from_binary(get_serialized_integrated_syntaxset(), COMPRESS_SYNTAXES)
 }
 
+/// An XDG base directory for git-dive, like `~/.config/git-dive/themes`
+fn default_dir(xdg_var: &str, home_fallback: &str, kind: &str) -> Option<std::path::PathBuf> {
+    let base = std::env::var_os(xdg_var)
+        .map(std::path::PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| {
+            Some(std::path::PathBuf::from(std::env::var_os("HOME")?).join(home_fallback))
+        })?;
+    Some(base.join("git-dive").join(kind))
+}
+
+/// Files directly in `dir` ending with `.{extension}`, sorted so later ones win consistently
+fn custom_files(dir: &std::path::Path, extension: &str) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(extension))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+pub(crate) const SYNTAXES_DIR: RawField<std::path::PathBuf> =
+    RawField::<std::path::PathBuf>::new("dive.syntaxesDir");
+pub(crate) const THEMES_DIR: RawField<std::path::PathBuf> =
+    RawField::<std::path::PathBuf>::new("dive.themesDir");
+
 pub(crate) fn to_anstyle_color(color: syntect::highlighting::Color) -> Option<anstyle::Color> {
     if color.a == 0 {
         // Themes can specify one of the user-configurable terminal colors by
@@ -50,6 +161,7 @@ pub(crate) fn to_anstyle_color(color: syntect::highlighting::Color) -> Option<an
     } else {
         Some(anstyle::RgbColor(color.r, color.g, color.b).into())
     }
+    .map(crate::color_depth::downsample)
 }
 
 /// Lazy-loaded syntaxes are already compressed, and we don't want to compress
@@ -88,6 +200,20 @@ fn from_binary<T: serde::de::DeserializeOwned>(v: &[u8], compressed: bool) -> T
         .expect("data integrated in binary is never faulty, but make sure `compressed` is in sync!")
 }
 
+fn asset_to_contents<T: serde::Serialize>(asset: &T, compressed: bool) -> Result<Vec<u8>> {
+    let config = bincode::config::legacy();
+    let mut contents = Vec::new();
+    if compressed {
+        let mut writer =
+            flate2::write::ZlibEncoder::new(&mut contents, flate2::Compression::best());
+        bincode::serde::encode_into_std_write(asset, &mut writer, config)?;
+        writer.finish()?;
+    } else {
+        bincode::serde::encode_into_std_write(asset, &mut contents, config)?;
+    }
+    Ok(contents)
+}
+
 fn asset_from_contents<T: serde::de::DeserializeOwned>(
     contents: &[u8],
     description: &str,
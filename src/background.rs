@@ -0,0 +1,191 @@
+//! Whether the terminal has a light or dark background, for `dive.theme = auto`
+
+use is_terminal::IsTerminal as _;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Background {
+    Light,
+    Dark,
+}
+
+impl Background {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Light => "light",
+            Self::Dark => "dark",
+        }
+    }
+}
+
+impl std::fmt::Display for Background {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/// How the [`Background`] was found
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Detection {
+    /// The terminal reported its background color
+    Query,
+    /// From `COLORFGBG`, set by some terminals
+    ColorFgBg,
+    /// Nothing to go on
+    Guess,
+}
+
+/// Ask the terminal for its background color, falling back to `COLORFGBG`
+///
+/// The terminal is only queried when stdout is one, so we don't hold up output to a pipe.
+pub(crate) fn detect() -> (Background, Detection) {
+    if std::io::stdout().is_terminal() {
+        if let Some(background) = query() {
+            return (background, Detection::Query);
+        }
+    }
+    if let Some(background) = std::env::var("COLORFGBG")
+        .ok()
+        .and_then(|value| from_colorfgbg(&value))
+    {
+        return (background, Detection::ColorFgBg);
+    }
+    (Background::Dark, Detection::Guess)
+}
+
+/// Send an OSC 11 query for the background color
+///
+/// It's followed by a DA1 query, which every terminal answers, so we know when to stop
+/// reading rather than always waiting out the timeout on terminals that ignore OSC 11.
+#[cfg(unix)]
+fn query() -> Option<Background> {
+    use std::io::Write as _;
+
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let response = tty
+        .write_all(b"\x1b]11;?\x1b\\\x1b[c")
+        .and_then(|()| tty.flush())
+        .ok()
+        .and_then(|()| read_response(&mut tty));
+    let _ = crossterm::terminal::disable_raw_mode();
+
+    let response = response?;
+    let response = String::from_utf8_lossy(&response);
+    let background = parse_osc11(&response);
+    log::trace!("OSC 11 response {response:?} is {background:?}");
+    background
+}
+
+/// Read up to the end of the DA1 response, giving up after [`QUERY_TIMEOUT`]
+///
+/// Bytes are read one at a time, once `poll` says one is ready, so we neither block past the
+/// timeout nor swallow keys typed after the response.
+#[cfg(unix)]
+fn read_response(tty: &mut std::fs::File) -> Option<Vec<u8>> {
+    use std::io::Read as _;
+
+    let deadline = std::time::Instant::now() + QUERY_TIMEOUT;
+    let mut response = Vec::new();
+    while !is_complete(&response) {
+        let remaining = deadline.checked_duration_since(std::time::Instant::now())?;
+        let timeout = rustix::event::Timespec::try_from(remaining).ok()?;
+        let mut fds = [rustix::event::PollFd::new(
+            &*tty,
+            rustix::event::PollFlags::IN,
+        )];
+        match rustix::event::poll(&mut fds, Some(&timeout)) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(rustix::io::Errno::INTR) => continue,
+            Err(_) => return None,
+        }
+        let mut byte = [0; 1];
+        if tty.read(&mut byte).ok()? == 0 {
+            return None;
+        }
+        response.push(byte[0]);
+    }
+    Some(response)
+}
+
+#[cfg(not(unix))]
+fn query() -> Option<Background> {
+    None
+}
+
+#[cfg(unix)]
+const QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Whether `response` ends with the answer to DA1, like `\e[?62;22c`
+#[cfg(unix)]
+fn is_complete(response: &[u8]) -> bool {
+    let Some(start) = response.windows(3).rposition(|w| w == b"\x1b[?") else {
+        return false;
+    };
+    response[start..].ends_with(b"c")
+}
+
+/// Read the color from a response like `\e]11;rgb:ffff/ffff/ffff\e\\`
+fn parse_osc11(response: &str) -> Option<Background> {
+    let (_, color) = response.split_once("\x1b]11;rgb:")?;
+    let color = color
+        .split(['\x1b', '\x07'])
+        .next()
+        .expect("split always has one element");
+    let mut channels = color.split('/').map(|channel| {
+        let max = 16_f64.powi(i32::try_from(channel.len()).ok()?) - 1.0;
+        let value = u16::from_str_radix(channel, 16).ok()?;
+        Some(f64::from(value) / max)
+    });
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+    Some(from_luminance(0.2126 * r + 0.7152 * g + 0.0722 * b))
+}
+
+/// Read the background from `COLORFGBG`, like `15;0` or `0;default;15`
+fn from_colorfgbg(value: &str) -> Option<Background> {
+    let background = value.rsplit(';').next()?.parse::<u8>().ok()?;
+    // White and the bright colors, except bright black
+    if background == 7 || (9..=15).contains(&background) {
+        Some(Background::Light)
+    } else {
+        Some(Background::Dark)
+    }
+}
+
+fn from_luminance(luminance: f64) -> Background {
+    if 0.5 < luminance {
+        Background::Light
+    } else {
+        Background::Dark
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn osc11() {
+        assert_eq!(
+            parse_osc11("\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;22c"),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            parse_osc11("\x1b]11;rgb:28/2c/34\x07"),
+            Some(Background::Dark)
+        );
+        assert_eq!(parse_osc11("\x1b[?62;22c"), None);
+    }
+
+    #[test]
+    fn colorfgbg() {
+        assert_eq!(from_colorfgbg("0;15"), Some(Background::Light));
+        assert_eq!(from_colorfgbg("15;default;0"), Some(Background::Dark));
+        assert_eq!(from_colorfgbg("default"), None);
+    }
+}
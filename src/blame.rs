@@ -1,10 +1,16 @@
 use anyhow::Context as _;
 use proc_exit::WithCodeResultExt;
 
+use crate::format::Format;
 use crate::git_pager::Pager;
 use crate::git2_config::Config;
 use crate::git2_config::DefaultField;
 use crate::git2_config::RawField;
+use crate::granularity::Attribution;
+use crate::granularity::Granularity;
+use crate::granularity::Tokenizer;
+use crate::granularity::Tokens;
+use crate::hunks::Hunks;
 
 pub(crate) fn blame(
     file_path: &std::path::Path,
@@ -13,157 +19,969 @@ pub(crate) fn blame(
 ) -> proc_exit::ExitResult {
     let colored_stdout =
         anstream::AutoStream::choice(&std::io::stdout()) != anstream::ColorChoice::Never;
-    let total_width = terminal_size::terminal_size()
-        .map(|(w, _h)| w.0)
-        .or_else(|| std::env::var_os("COLUMNS").and_then(|s| s.to_str()?.parse::<u16>().ok()))
-        .unwrap_or(80) as usize;
 
     let cwd = std::env::current_dir().with_code(proc_exit::Code::FAILURE)?;
     let repo = git2::Repository::discover(&cwd).with_code(proc_exit::Code::FAILURE)?;
     config.add_repo(&repo);
-    let theme = config.get(&THEME);
 
     let rel_path = to_repo_relative(&cwd, file_path, &repo).with_code(proc_exit::Code::FAILURE)?;
 
-    let rev_obj = repo
-        .revparse_single(&args.rev)
+    let syntax_set = crate::assets::load_syntaxes(config);
+    let theme_set = crate::assets::load_themes(config);
+    let blamer = Blamer::new(&repo, &syntax_set, &theme_set, config, args, colored_stdout)
         .with_code(proc_exit::Code::FAILURE)?;
-    let rev_commit = rev_obj
-        .peel_to_commit()
-        .map_err(|_| {
-            anyhow::format_err!(
-                "Unsupported rev `{}` ({})",
-                args.rev,
-                rev_obj.kind().map(|k| k.str()).unwrap_or("unknown")
-            )
-        })
-        .with_code(proc_exit::Code::FAILURE)?;
-    let mut settings = git2::BlameOptions::new();
-    settings
-        .track_copies_same_file(true)
-        .track_copies_same_commit_moves(true)
-        .track_copies_same_commit_copies(true)
-        .track_copies_any_commit_copies(true)
-        .first_parent(true)
-        .ignore_whitespace(true)
-        .newest_commit(rev_commit.id());
-    let blame = repo
-        .blame_file(&rel_path, Some(&mut settings))
-        .with_code(proc_exit::Code::FAILURE)?;
-    let mut annotations = Annotations::new(&repo, &blame);
-    annotations
-        .relative_origin(&repo, &args.rev)
+    let blamed = blamer
+        .blame_args(args, &rel_path)
         .with_code(proc_exit::Code::FAILURE)?;
+    let line = blamed.ranges.first().map(|(start, _)| *start).unwrap_or(1);
 
-    let file = read_file(&repo, &args.rev, &rel_path).with_code(proc_exit::Code::FAILURE)?;
-
-    let syntax_set = crate::assets::load_syntaxes();
-    let theme_set = crate::assets::load_themes();
-    let theme = theme_set
-        .get(&theme)
-        .or_else(|| theme_set.get(THEME_DEFAULT))
-        .expect("default theme is present");
-
-    let syntax = syntax_set
-        .find_syntax_for_file(file_path)
-        .with_code(proc_exit::Code::FAILURE)?
-        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
-
-    let file = convert_file(&file, file_path).with_code(proc_exit::Code::FAILURE)?;
-
-    let line_count = file.lines().count();
-    let line_count_width = line_count.to_string().len(); // bytes = chars = columns with digits
-    let sep = "│";
-    let space_count = 3;
-    let origin_width = annotations
-        .notes
-        .values()
-        .map(|a| {
-            // HACK: when we support more than IDs, we'll need to respect UTF-8
-            a.origin().len()
-        })
-        .max()
-        .unwrap_or(0);
-    let gutter_width = origin_width + line_count_width + sep.len() + space_count;
+    show(&blamer, blamed, line, config, args)
+}
 
-    let code_width = total_width.saturating_sub(gutter_width);
+/// Write out `blamed` in the format from `args`, or browse it starting at `line`
+pub(crate) fn show(
+    blamer: &Blamer<'_>,
+    blamed: Blamed<'_>,
+    line: usize,
+    config: &mut Config,
+    args: &crate::args::Args,
+) -> proc_exit::ExitResult {
+    let total_width = terminal_size::terminal_size()
+        .map(|(w, _h)| w.0)
+        .or_else(|| std::env::var_os("COLUMNS").and_then(|s| s.to_str()?.parse::<u16>().ok()))
+        .unwrap_or(80) as usize;
+    let interactive = args.interactive || config.get(&INTERACTIVE);
 
-    let mut highlighter = if colored_stdout {
-        Highlighter::enabled(syntax, theme)
-    } else {
-        Highlighter::disabled()
-    };
+    let format = args.format();
+    if format != Format::Text {
+        let lines = blamed.lines().collect::<Vec<_>>();
+        let is_selected = |line_num| blamed.is_selected(line_num);
+        let mut stdout = std::io::stdout().lock();
+        let result = if format == Format::Json {
+            crate::format::write_json(
+                &mut stdout,
+                &blamed.path,
+                &blamed.rev,
+                &blamed.head,
+                &blamed.hunks,
+                &blamed.annotations,
+                blamed.tokens.as_ref(),
+                &lines,
+                is_selected,
+            )
+        } else {
+            crate::format::write_porcelain(
+                &mut stdout,
+                format,
+                blamer.repo,
+                &blamed.hunks,
+                &blamed.annotations,
+                &lines,
+                is_selected,
+            )
+        };
+        return result.with_code(proc_exit::Code::FAILURE);
+    }
+
+    if interactive && crate::tui::is_available() {
+        return crate::tui::run(blamer, blamed, line).with_code(proc_exit::Code::FAILURE);
+    }
 
-    let reset = if colored_stdout {
+    let rows = blamed
+        .render(blamer, total_width, blamer.wrap, |line_num| {
+            blamed.is_selected(line_num)
+        })
+        .with_code(proc_exit::Code::FAILURE)?;
+    let reset = if blamer.colored {
         anstyle::Reset.render().to_string()
     } else {
         "".to_owned()
     };
-    let gutter_style = if colored_stdout {
-        gutter_style(highlighter.theme()).render().to_string()
-    } else {
-        "".to_owned()
-    };
-    let wrap = textwrap::Options::new(code_width)
-        .break_words(false)
-        .wrap_algorithm(textwrap::WrapAlgorithm::FirstFit);
 
     let pager = config.get(&crate::git2_config::PAGER);
     let mut pager = Pager::stdout(&pager);
     let mut pager = pager.start();
     let pager = pager.as_writer().with_code(proc_exit::Code::FAILURE)?;
-    let mut prev_hunk_id = git2::Oid::zero();
-    for (line_num, file_line) in file.lines().enumerate() {
-        let line_num = line_num + 1;
+    for row in rows {
+        let Row {
+            gutter,
+            style,
+            code,
+            ..
+        } = row;
+        let style = style.render();
+        let _ = write!(pager, "{style}{gutter}{reset}{code}\n{reset}");
+    }
+
+    let tints = if blamer.colored {
+        blamed.tints(blamer.theme)
+    } else {
+        Vec::new()
+    };
+    if !tints.is_empty() {
+        let _ = writeln!(pager);
+        let _ = writeln!(pager, "Tokens from other commits:");
+        for (id, style) in tints {
+            let ann = blamed
+                .annotations
+                .get(id)
+                .expect("all tokens are annotated");
+            let _ = writeln!(
+                pager,
+                "  {}{}{reset} {}",
+                style.render(),
+                ann.origin(),
+                ann.summary()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Shared state for blaming files, possibly at several revisions
+pub(crate) struct Blamer<'r> {
+    repo: &'r git2::Repository,
+    syntax_set: &'r syntect::parsing::SyntaxSet,
+    theme: &'r syntect::highlighting::Theme,
+    colored: bool,
+    ignored: std::collections::HashSet<git2::Oid>,
+    mark_ignored: bool,
+    mark_unblamable: bool,
+    gutter: crate::gutter::Gutter,
+    color_by: crate::palette::ColorBy,
+    wrap: Overflow,
+    algorithm: Algorithm,
+    pull_requests: crate::merge::PullRequests,
+    granularity: Granularity,
+    origin_style: crate::origin::OriginStyle,
+    syntax_mapping: crate::syntax_mapping::SyntaxMapping,
+}
+
+impl<'r> Blamer<'r> {
+    pub(crate) fn new(
+        repo: &'r git2::Repository,
+        syntax_set: &'r syntect::parsing::SyntaxSet,
+        theme_set: &'r crate::assets::LazyThemeSet,
+        config: &Config,
+        args: &crate::args::Args,
+        colored: bool,
+    ) -> anyhow::Result<Self> {
+        let theme = resolve_theme(config, colored);
+        let theme = theme_set
+            .get(&theme.name)
+            .or_else(|| theme_set.get(THEME_DEFAULT))
+            .expect("default theme is present");
+        let ignored = ignored_revs(repo, config, args)?;
+        let pull_requests =
+            crate::merge::PullRequests::new(config.get(&PULL_REQUEST_PATTERN).as_deref())?;
+        let syntax_mapping = crate::syntax_mapping::SyntaxMapping::new(syntax_set, config, args)?;
+        Ok(Self {
+            repo,
+            syntax_set,
+            theme,
+            colored,
+            ignored,
+            mark_ignored: config.get(&MARK_IGNORED_LINES),
+            mark_unblamable: config.get(&MARK_UNBLAMABLE_LINES),
+            gutter: config.get(&GUTTER),
+            color_by: config.get(&COLOR_BY),
+            wrap: config.get(&WRAP),
+            algorithm: Algorithm::new(config, args),
+            pull_requests,
+            granularity: args.granularity,
+            origin_style: config.get(&ORIGIN_STYLE),
+            syntax_mapping,
+        })
+    }
 
-        let file_line = if line_num == 1 {
-            file_line.strip_prefix('\u{feff}').unwrap_or(file_line)
+    /// Blame `rel_path` at the revision selected on the command-line
+    pub(crate) fn blame_args(
+        &self,
+        args: &crate::args::Args,
+        rel_path: &std::path::Path,
+    ) -> anyhow::Result<Blamed<'r>> {
+        let mut blamed = if let Some(range) = args.reverse.as_deref() {
+            let (old, new) = split_range(range);
+            self.reverse(old, new, rel_path, &args.line_range)?
+        } else if args.cached {
+            self.uncommitted(Uncommitted::Index, rel_path, &args.line_range)?
+        } else if let Some(rev) = args.rev.as_deref() {
+            if args.follow {
+                let rel_path = follow_path(self.repo, rev, rel_path)?;
+                self.blame(rev, &rel_path, &args.line_range)?
+            } else {
+                self.blame(rev, rel_path, &args.line_range)?
+            }
+        } else if Uncommitted::is_dirty(self.repo, rel_path) {
+            self.uncommitted(Uncommitted::Worktree, rel_path, &args.line_range)?
         } else {
-            file_line
+            self.blame("HEAD", rel_path, &args.line_range)?
         };
+        if let Some(tip) = args.next.as_deref() {
+            self.next_changes(&mut blamed, tip)?;
+        }
+        Ok(blamed)
+    }
 
-        let file_line = highlighter
-            .highlight_line(file_line, &syntax_set)
-            .with_code(proc_exit::Code::FAILURE)?;
-        #[allow(clippy::never_loop)]
-        for (i, visual_line) in textwrap::wrap(&file_line, &wrap).into_iter().enumerate() {
-            let origin = if i == 0 {
-                let hunk = blame.get_line(line_num).unwrap_or_else(|| {
-                    panic!("Mismatch in line numbers between dive ({line_num}) and git2")
-                });
-                let hunk_id = hunk.orig_commit_id();
-                if hunk_id != prev_hunk_id {
-                    prev_hunk_id = hunk_id;
-                    let ann = annotations
-                        .notes
-                        .get(&hunk_id)
-                        .expect("all blame hunks are annotated");
-                    ann.origin()
-                } else {
-                    "⋮"
+    /// Find when each line of `blamed` was next changed on the way to `tip`
+    fn next_changes(&self, blamed: &mut Blamed<'r>, tip: &str) -> anyhow::Result<()> {
+        let rev_commit = peel_to_commit(self.repo, &blamed.rev)?;
+        let tip_commit = peel_to_commit(self.repo, tip)?;
+        let lines = crate::hunks::forward(self.repo, &rev_commit, &tip_commit, &blamed.path)?
+            .into_iter()
+            .map(|(_, next)| next)
+            .collect::<Vec<_>>();
+        let mut annotations = Annotations::default();
+        for id in lines.iter().flatten() {
+            annotations.add(self.repo, *id, None);
+        }
+        annotations.relative_origin(self.repo, tip, self.origin_style, &self.pull_requests)?;
+        blamed.next = Some(NextChanges { lines, annotations });
+        Ok(())
+    }
+
+    /// Blame `path` as of `rev`
+    pub(crate) fn blame(
+        &self,
+        rev: &str,
+        path: &std::path::Path,
+        line_ranges: &[crate::line_range::LineRange],
+    ) -> anyhow::Result<Blamed<'r>> {
+        let (file, syntax, ranges) = self.read(rev, path, line_ranges)?;
+
+        let rev_commit = peel_to_commit(self.repo, rev)?;
+        let mut settings = self.blame_options();
+        if let (Some((min_line, _)), Some((_, max_line))) = (ranges.first(), ranges.last()) {
+            settings.min_line(*min_line).max_line(*max_line);
+        }
+        let hunks = Hunks::blame(self.repo, path, rev_commit.id(), &mut settings)?;
+        // Re-attributing is done against whole-file blames of the parents
+        settings.min_line(0).max_line(0);
+        let hunks = hunks.ignore_revs(self.repo, &self.ignored, &mut settings)?;
+
+        let mut blamed = self.annotate(rev, rev, None, path, file, syntax, ranges, hunks)?;
+        self.attribute(&mut blamed, None)?;
+        Ok(blamed)
+    }
+
+    /// Annotate each line of `path` at `old` with the last commit it was present in on the way
+    /// to `new`
+    pub(crate) fn reverse(
+        &self,
+        old: &str,
+        new: &str,
+        path: &std::path::Path,
+        line_ranges: &[crate::line_range::LineRange],
+    ) -> anyhow::Result<Blamed<'r>> {
+        let (file, syntax, ranges) = self.read(old, path, line_ranges)?;
+
+        let old_commit = peel_to_commit(self.repo, old)?;
+        let new_commit = peel_to_commit(self.repo, new)?;
+        let hunks = Hunks::reverse(self.repo, &old_commit, &new_commit, path)?;
+
+        self.annotate(old, new, None, path, file, syntax, ranges, hunks)
+    }
+
+    /// Blame the version of `path` in the working tree or index, on top of `HEAD`
+    pub(crate) fn uncommitted(
+        &self,
+        uncommitted: Uncommitted,
+        path: &std::path::Path,
+        line_ranges: &[crate::line_range::LineRange],
+    ) -> anyhow::Result<Blamed<'r>> {
+        let buffer = uncommitted.read(self.repo, path)?;
+        let file = convert_file(&buffer, path)?;
+        let syntax = self.find_syntax(path, &file);
+        let ranges = resolve_ranges(line_ranges, &file, syntax, self.syntax_set)?;
+
+        let head = "HEAD";
+        let head_commit = peel_to_commit(self.repo, head)?;
+        // `min_line` and `max_line` are for `HEAD`'s version, so we can't use them
+        let mut settings = self.blame_options();
+        let hunks = Hunks::blame_buffer(self.repo, path, head_commit.id(), &buffer, &mut settings)?;
+        let hunks = hunks.ignore_revs(self.repo, &self.ignored, &mut settings)?;
+
+        let mut blamed = self.annotate(
+            uncommitted.label(),
+            head,
+            Some(uncommitted),
+            path,
+            file,
+            syntax,
+            ranges,
+            hunks,
+        )?;
+        self.attribute(&mut blamed, Some(uncommitted))?;
+        Ok(blamed)
+    }
+
+    fn blame_options(&self) -> git2::BlameOptions {
+        let algorithm = &self.algorithm;
+        let mut settings = git2::BlameOptions::new();
+        settings
+            .track_copies_same_file(algorithm.moves)
+            .track_copies_same_commit_moves(1 <= algorithm.copies)
+            .track_copies_same_commit_copies(2 <= algorithm.copies)
+            .track_copies_any_commit_copies(3 <= algorithm.copies)
+            .first_parent(algorithm.first_parent)
+            .ignore_whitespace(algorithm.ignore_whitespace);
+        settings
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn read(
+        &self,
+        rev: &str,
+        path: &std::path::Path,
+        line_ranges: &[crate::line_range::LineRange],
+    ) -> anyhow::Result<(
+        String,
+        &'r syntect::parsing::SyntaxReference,
+        Vec<(usize, usize)>,
+    )> {
+        let file = read_file(self.repo, rev, path)?;
+        let file = convert_file(&file, path)?;
+        let syntax = self.find_syntax(path, &file);
+        let ranges = resolve_ranges(line_ranges, &file, syntax, self.syntax_set)?;
+        Ok((file, syntax, ranges))
+    }
+
+    /// Resolve `-L` arguments against `path` as of `rev`
+    pub(crate) fn resolve_ranges(
+        &self,
+        rev: &str,
+        path: &std::path::Path,
+        line_ranges: &[crate::line_range::LineRange],
+    ) -> anyhow::Result<Vec<(usize, usize)>> {
+        let (_, _, ranges) = self.read(rev, path, line_ranges)?;
+        Ok(ranges)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn annotate(
+        &self,
+        rev: &str,
+        head: &str,
+        uncommitted: Option<Uncommitted>,
+        path: &std::path::Path,
+        file: String,
+        syntax: &'r syntect::parsing::SyntaxReference,
+        ranges: Vec<(usize, usize)>,
+        hunks: Hunks,
+    ) -> anyhow::Result<Blamed<'r>> {
+        let mut annotations = Annotations::new(self.repo, &hunks, uncommitted);
+        annotations.relative_origin(self.repo, head, self.origin_style, &self.pull_requests)?;
+        Ok(Blamed {
+            rev: rev.to_owned(),
+            head: head.to_owned(),
+            path: path.to_owned(),
+            file,
+            syntax,
+            ranges,
+            hunks,
+            annotations,
+            tokens: None,
+            next: None,
+        })
+    }
+
+    /// Find the commit behind each word or token of the selected lines, for `--granularity`
+    ///
+    /// Like `dive.firstParent`, changes from merged branches are attributed to the merge.
+    fn attribute(
+        &self,
+        blamed: &mut Blamed<'r>,
+        uncommitted: Option<Uncommitted>,
+    ) -> anyhow::Result<()> {
+        if self.granularity == Granularity::Line {
+            return Ok(());
+        }
+        let tokenizer = Tokenizer::new(self.granularity, blamed.syntax, self.syntax_set);
+        let mut attribution =
+            Attribution::new(&tokenizer, &blamed.file, |line| blamed.is_selected(line));
+        if uncommitted.is_some() {
+            let head = peel_to_commit(self.repo, &blamed.head)?;
+            let old = match head.tree()?.get_path(&blamed.path) {
+                Ok(entry) => {
+                    let blob = entry.to_object(self.repo)?.peel_to_blob()?;
+                    Some(convert_file(blob.content(), &blamed.path)?)
                 }
-            } else {
-                "⋮"
+                Err(_) => None,
             };
+            attribution.step(git2::Oid::zero(), old)?;
+        }
+        attribution.walk(self.repo, &blamed.head, &blamed.path)?;
+        let tokens = attribution.finish(|line| blamed.hunk(line).map(|h| h.commit_id()));
 
-            let line_num = if i == 0 {
-                line_num.to_string()
-            } else {
-                "⋮".to_owned()
-            };
-            let _ = write!(
-                pager,
-                "{gutter_style}{origin:origin_width$} {line_num:>line_count_width$} {sep} {reset}{visual_line}\n{reset}"
+        for id in tokens.commits() {
+            blamed.annotations.add(self.repo, id, uncommitted);
+        }
+        blamed.annotations.relative_origin(
+            self.repo,
+            &blamed.head,
+            self.origin_style,
+            &self.pull_requests,
+        )?;
+        blamed.tokens = Some(tokens);
+        Ok(())
+    }
+
+    pub(crate) fn repo(&self) -> &'r git2::Repository {
+        self.repo
+    }
+
+    pub(crate) fn syntax_set(&self) -> &'r syntect::parsing::SyntaxSet {
+        self.syntax_set
+    }
+
+    /// Pick a syntax for `path` with `file` as its contents
+    pub(crate) fn find_syntax(
+        &self,
+        path: &std::path::Path,
+        file: &str,
+    ) -> &'r syntect::parsing::SyntaxReference {
+        self.syntax_mapping
+            .find(self.repo, self.syntax_set, path, file)
+    }
+
+    pub(crate) fn theme(&self) -> &'r syntect::highlighting::Theme {
+        self.theme
+    }
+
+    pub(crate) fn colored(&self) -> bool {
+        self.colored
+    }
+
+    pub(crate) fn wrap(&self) -> Overflow {
+        self.wrap
+    }
+}
+
+/// How blame is assigned
+struct Algorithm {
+    first_parent: bool,
+    ignore_whitespace: bool,
+    moves: bool,
+    /// Like the number of times `-C` is passed to `git blame`
+    copies: u8,
+}
+
+impl Algorithm {
+    fn new(config: &Config, args: &crate::args::Args) -> Self {
+        Self {
+            first_parent: args
+                .first_parent()
+                .unwrap_or_else(|| config.get(&FIRST_PARENT)),
+            ignore_whitespace: args
+                .ignore_whitespace()
+                .unwrap_or_else(|| config.get(&IGNORE_WHITESPACE)),
+            moves: args.moves().unwrap_or_else(|| config.get(&DETECT_MOVES)),
+            copies: args
+                .copies()
+                .unwrap_or_else(|| config.get(&DETECT_COPIES).clamp(0, 3) as u8)
+                .min(3),
+        }
+    }
+}
+
+/// A file, annotated with where each line came from
+pub(crate) struct Blamed<'r> {
+    /// The revision the file was read from
+    pub(crate) rev: String,
+    /// The revision origins are relative to
+    pub(crate) head: String,
+    /// Relative to the root of the repo
+    pub(crate) path: std::path::PathBuf,
+    file: String,
+    syntax: &'r syntect::parsing::SyntaxReference,
+    /// Lines selected with `-L`, with an empty list meaning all lines
+    ranges: Vec<(usize, usize)>,
+    hunks: Hunks,
+    annotations: Annotations,
+    /// From `--granularity`, when blaming more finely than lines
+    tokens: Option<Tokens>,
+    /// From `--next`
+    next: Option<NextChanges>,
+}
+
+/// The commit that next changed or removed each line, after the blamed revision
+struct NextChanges {
+    /// For each line, `None` when it was left alone
+    lines: Vec<Option<git2::Oid>>,
+    /// Named relative to the tip
+    annotations: Annotations,
+}
+
+impl NextChanges {
+    fn get(&self, line_num: usize) -> Option<&Annotation> {
+        let id = (*self.lines.get(line_num - 1)?)?;
+        self.annotations.get(id)
+    }
+}
+
+impl Blamed<'_> {
+    pub(crate) fn lines(&self) -> std::str::Lines<'_> {
+        self.file
+            .strip_prefix('\u{feff}')
+            .unwrap_or(&self.file)
+            .lines()
+    }
+
+    pub(crate) fn line_count(&self) -> usize {
+        self.lines().count()
+    }
+
+    pub(crate) fn is_selected(&self, line_num: usize) -> bool {
+        self.ranges.is_empty()
+            || self
+                .ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&line_num))
+    }
+
+    pub(crate) fn hunk(&self, line_num: usize) -> Option<&crate::hunks::Hunk> {
+        self.hunks.get_line(line_num)
+    }
+
+    /// Parts of `line_num` from a commit other than the line's, without surrounding whitespace
+    fn marked_spans<'s>(
+        &'s self,
+        line_num: usize,
+        line: &'s str,
+    ) -> impl Iterator<Item = (std::ops::Range<usize>, git2::Oid)> + 's {
+        let line_commit = self.hunk(line_num).map(|h| h.commit_id());
+        self.tokens
+            .iter()
+            .flat_map(move |tokens| tokens.line(line_num))
+            .filter(move |span| Some(span.commit) != line_commit)
+            .filter_map(move |span| {
+                let text = line.get(span.range.clone())?;
+                let trimmed = text.trim_start();
+                let start = span.range.start + (text.len() - trimmed.len());
+                let end = start + trimmed.trim_end().len();
+                (start < end).then_some((start..end, span.commit))
+            })
+    }
+
+    /// Styles for the commits behind [`Blamed::marked_spans`] of selected lines, in the order
+    /// they first appear
+    pub(crate) fn tints(
+        &self,
+        theme: &syntect::highlighting::Theme,
+    ) -> Vec<(git2::Oid, anstyle::Style)> {
+        let mut commits = Vec::new();
+        for (line_num, line) in self.lines().enumerate() {
+            let line_num = line_num + 1;
+            if !self.is_selected(line_num) {
+                continue;
+            }
+            for (_, commit) in self.marked_spans(line_num, line) {
+                if !commits.contains(&commit) {
+                    commits.push(commit);
+                }
+            }
+        }
+        let tints = crate::palette::tints(theme, commits.len());
+        commits.into_iter().zip(tints).collect()
+    }
+
+    /// The commit `line_num` is attributed to
+    pub(crate) fn annotation(&self, line_num: usize) -> Option<&Annotation> {
+        let hunk = self.hunks.get_line(line_num)?;
+        self.annotations.get(hunk.commit_id())
+    }
+
+    /// Where to blame next to see what came before `line_num`
+    ///
+    /// Returns the revision, path, and line number in that revision that best corresponds to
+    /// `line_num`.
+    pub(crate) fn parent_of(
+        &self,
+        repo: &git2::Repository,
+        line_num: usize,
+    ) -> anyhow::Result<(String, std::path::PathBuf, usize)> {
+        let hunk = self
+            .hunks
+            .get_line(line_num)
+            .with_context(|| format!("line {line_num} was not blamed"))?;
+        let annotation = self
+            .annotations
+            .get(hunk.commit_id())
+            .expect("all blame hunks are annotated");
+        if hunk.commit_id().is_zero() {
+            return self.head_of(repo, line_num);
+        }
+        let commit = repo.find_commit(hunk.commit_id())?;
+        let parent = commit
+            .parent(0)
+            .map_err(|_| anyhow::format_err!("{} is a root commit", annotation.origin()))?;
+        let path = hunk.orig_path();
+        let not_found =
+            || anyhow::format_err!("{} was added in {}", path.display(), annotation.origin());
+        let old = parent.tree()?.get_path(path).map_err(|_| not_found())?;
+        let old = old.to_object(repo)?.peel_to_blob()?;
+        let new = commit.tree()?.get_path(path)?;
+        let new = new.to_object(repo)?.peel_to_blob()?;
+        let map = crate::diff::LineMap::from_buffers(old.content(), new.content())?;
+
+        // Lines without a counterpart, like additions, stay near where they were added
+        let orig_line = hunk.orig_start_line() + (line_num - hunk.final_start_line());
+        let parent_line = (1..=orig_line)
+            .rev()
+            .find_map(|line| map.new_to_old_fuzzy(line))
+            .unwrap_or(1);
+
+        Ok((
+            parent_rev(annotation.origin()),
+            path.to_owned(),
+            parent_line,
+        ))
+    }
+
+    /// Where `line_num` of an uncommitted file is in `HEAD`
+    fn head_of(
+        &self,
+        repo: &git2::Repository,
+        line_num: usize,
+    ) -> anyhow::Result<(String, std::path::PathBuf, usize)> {
+        let head = "HEAD";
+        let not_found = || anyhow::format_err!("{} is not in {head}", self.path.display());
+        let old = peel_to_commit(repo, head)?
+            .tree()?
+            .get_path(&self.path)
+            .map_err(|_| not_found())?;
+        let old = old.to_object(repo)?.peel_to_blob()?;
+        let map = crate::diff::LineMap::from_buffers(old.content(), self.file.as_bytes())?;
+        let head_line = (1..=line_num)
+            .rev()
+            .find_map(|line| map.new_to_old_fuzzy(line))
+            .unwrap_or(1);
+        Ok((head.to_owned(), self.path.clone(), head_line))
+    }
+
+    /// Lay out the annotated file for a terminal `total_width` columns wide
+    pub(crate) fn render(
+        &self,
+        blamer: &Blamer<'_>,
+        total_width: usize,
+        wrap: Overflow,
+        is_selected: impl Fn(usize) -> bool,
+    ) -> anyhow::Result<Vec<Row>> {
+        let mark_ignored = blamer.mark_ignored;
+        let mark_unblamable = blamer.mark_unblamable;
+
+        let line_count = self.line_count();
+        let line_count_width = line_count.to_string().len(); // bytes = chars = columns with digits
+        let sep = "│";
+        let space_count = 3;
+        let layout = crate::gutter::Layout::new(&blamer.gutter, self.annotations.notes.values());
+        let mark_width = usize::from(
+            self.hunks
+                .iter()
+                .any(|h| !hunk_mark(h, mark_ignored, mark_unblamable).is_empty()),
+        );
+        // Only call out paths for code that was moved or copied from elsewhere
+        let path_width = self
+            .hunks
+            .iter()
+            .filter(|h| h.orig_path() != self.path)
+            .map(|h| crate::gutter::display_width(&h.orig_path().to_string_lossy()))
+            .max()
+            .map(|w| w.min(MAX_PATH_WIDTH));
+        let origin_width = mark_width + layout.width() + path_width.map(|w| w + 1).unwrap_or(0);
+        let next_arrow = "→ ";
+        let next_layout = self.next.as_ref().map(|next| {
+            crate::gutter::Layout::new(
+                &crate::gutter::Gutter::default(),
+                next.annotations.notes.values(),
+            )
+        });
+        let next_width = next_layout
+            .as_ref()
+            .map(|l| crate::gutter::display_width(next_arrow) + l.width() + 1)
+            .unwrap_or(0);
+        let gutter_width = origin_width
+            + next_width
+            + line_count_width
+            + crate::gutter::display_width(sep)
+            + space_count;
+
+        let code_width = total_width.saturating_sub(gutter_width);
+
+        let tints = if blamer.colored {
+            self.tints(blamer.theme)
+                .into_iter()
+                .collect::<std::collections::HashMap<_, _>>()
+        } else {
+            Default::default()
+        };
+        let (mut highlighter, palette) = if blamer.colored {
+            let palette = crate::palette::Palette::new(
+                blamer.color_by,
+                blamer.theme,
+                self.annotations.notes.iter().map(|(id, a)| (*id, a)),
             );
-            // HACK: Truncate until we fix our coloring of wrapped lines
-            break;
+            (Highlighter::enabled(self.syntax, blamer.theme), palette)
+        } else {
+            (Highlighter::disabled(), crate::palette::Palette::plain())
+        };
+
+        let options = textwrap::Options::new(code_width.max(1))
+            .break_words(true)
+            .wrap_algorithm(textwrap::WrapAlgorithm::FirstFit);
+
+        let mut rows = Vec::new();
+        let mut prev_hunk_id = None;
+        let mut prev_next_id = None;
+        for (line_num, file_line) in self.lines().enumerate() {
+            let line_num = line_num + 1;
+            if !is_selected(line_num) {
+                // Always show the origin at the start of each range
+                prev_hunk_id = None;
+                prev_next_id = None;
+                continue;
+            }
+
+            let marks = self
+                .marked_spans(line_num, file_line)
+                .filter_map(|(range, commit)| Some((range, *tints.get(&commit)?)))
+                .collect::<Vec<_>>();
+            let file_line = highlighter.highlight_marked(file_line, blamer.syntax_set, &marks)?;
+            let hunk = self.hunks.get_line(line_num).unwrap_or_else(|| {
+                panic!("Mismatch in line numbers between dive ({line_num}) and git2")
+            });
+            let style = palette.get(hunk.commit_id());
+            let visual_lines = match wrap {
+                Overflow::Wrap => carry_styles(textwrap::wrap(&file_line, &options)),
+                Overflow::Truncate => {
+                    let mut visual_lines = carry_styles(textwrap::wrap(&file_line, &options));
+                    visual_lines.truncate(1);
+                    visual_lines
+                }
+                Overflow::None => vec![file_line],
+            };
+            for (i, visual_line) in visual_lines.into_iter().enumerate() {
+                let origin = if i == 0 {
+                    let mark = hunk_mark(hunk, mark_ignored, mark_unblamable);
+                    let hunk_id = Some((hunk.commit_id(), mark, hunk.orig_path()));
+                    if hunk_id != prev_hunk_id {
+                        prev_hunk_id = hunk_id;
+                        let ann = self
+                            .annotations
+                            .notes
+                            .get(&hunk.commit_id())
+                            .expect("all blame hunks are annotated");
+                        let mut origin = format!("{mark}{}", layout.render(ann));
+                        if let Some(path_width) = path_width {
+                            let path = if hunk.orig_path() != self.path {
+                                hunk.orig_path().to_string_lossy()
+                            } else {
+                                "".into()
+                            };
+                            let path = crate::gutter::truncate_start(&path, path_width);
+                            origin.push(' ');
+                            origin.push_str(&crate::gutter::pad(&path, path_width));
+                        }
+                        origin
+                    } else {
+                        "⋮".to_owned()
+                    }
+                } else {
+                    "⋮".to_owned()
+                };
+
+                let next = match (&self.next, &next_layout) {
+                    (Some(next), Some(next_layout)) => {
+                        let next_id = next.lines.get(line_num - 1).copied().flatten();
+                        let column = match next.get(line_num) {
+                            Some(_) if 0 < i => "".to_owned(),
+                            Some(_) if next_id == prev_next_id => "⋮".to_owned(),
+                            Some(ann) => format!("{next_arrow}{}", next_layout.render(ann)),
+                            None => "".to_owned(),
+                        };
+                        prev_next_id = next_id;
+                        format!("{} ", crate::gutter::pad(&column, next_width - 1))
+                    }
+                    _ => String::new(),
+                };
+                let line_num_column = if i == 0 {
+                    line_num.to_string()
+                } else {
+                    "⋮".to_owned()
+                };
+                let origin = crate::gutter::pad(&origin, origin_width);
+                rows.push(Row {
+                    line: line_num,
+                    gutter: format!("{origin} {next}{line_num_column:>line_count_width$} {sep} "),
+                    style,
+                    code: visual_line,
+                });
+            }
         }
+        Ok(rows)
     }
+}
 
-    Ok(())
+/// A visual line of a [`Blamed`] file
+pub(crate) struct Row {
+    /// The line in the file this is for
+    pub(crate) line: usize,
+    /// Unstyled annotations
+    pub(crate) gutter: String,
+    /// Style for `gutter`
+    pub(crate) style: anstyle::Style,
+    /// Styled content
+    pub(crate) code: String,
 }
 
-fn to_repo_relative(
+/// Restore styling at the start of each wrapped line and reset it at the end
+///
+/// [`Highlighter::highlight_line`] only styles the start of each region, so continuation lines
+/// would otherwise lose their color and styling would bleed into the gutter.
+fn carry_styles(visual_lines: Vec<std::borrow::Cow<'_, str>>) -> Vec<String> {
+    const RESET: &str = "\x1b[0m";
+
+    let mut active = String::new();
+    let mut carried = Vec::with_capacity(visual_lines.len());
+    for visual_line in visual_lines {
+        let mut line = active.clone();
+        line.push_str(&visual_line);
+        let mut rest = visual_line.as_ref();
+        while let Some(start) = rest.find("\x1b[") {
+            rest = &rest[start..];
+            let Some(end) = rest.find('m') else {
+                break;
+            };
+            let escape = &rest[..=end];
+            if escape == RESET || escape == "\x1b[m" {
+                active.clear();
+            } else {
+                active.push_str(escape);
+            }
+            rest = &rest[end + 1..];
+        }
+        if !active.is_empty() {
+            line.push_str(RESET);
+        }
+        carried.push(line);
+    }
+    carried
+}
+
+/// How to fit long lines into the terminal, from `dive.wrap`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub(crate) enum Overflow {
+    /// Continue on the next row
+    #[default]
+    Wrap,
+    /// Cut off at the edge of the terminal
+    Truncate,
+    /// Leave it to the pager, like `less -S`
+    None,
+}
+
+impl Overflow {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Wrap => "wrap",
+            Self::Truncate => "truncate",
+            Self::None => "none",
+        }
+    }
+}
+
+impl std::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl std::str::FromStr for Overflow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" | "true" => Ok(Self::Wrap),
+            "truncate" => Ok(Self::Truncate),
+            "none" | "false" => Ok(Self::None),
+            _ => Err(anyhow::format_err!(
+                "unsupported wrap value: `{s}`, expected one of `wrap`, `truncate`, `none`"
+            )),
+        }
+    }
+}
+
+impl crate::git2_config::Parseable for Overflow {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        <Self as std::str::FromStr>::from_str(s)
+    }
+}
+
+/// Changes that aren't in a commit yet
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Uncommitted {
+    Worktree,
+    Index,
+}
+
+impl Uncommitted {
+    /// Stand-in for the origin of uncommitted lines
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            Self::Worktree => "WORKTREE",
+            Self::Index => "INDEX",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Worktree => "working tree",
+            Self::Index => "index",
+        }
+    }
+
+    fn read(&self, repo: &git2::Repository, rel_path: &std::path::Path) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Worktree => {
+                let workdir = repo
+                    .workdir()
+                    .context("Cannot blame the working tree of a bare repository")?;
+                let path = workdir.join(rel_path);
+                std::fs::read(&path).with_context(|| format!("Could not read {}", path.display()))
+            }
+            Self::Index => {
+                let index = repo.index()?;
+                let entry = index
+                    .get_path(rel_path, 0)
+                    .with_context(|| format!("{} is not in the index", rel_path.display()))?;
+                let blob = repo.find_blob(entry.id)?;
+                Ok(blob.content().to_owned())
+            }
+        }
+    }
+
+    /// Whether `rel_path` has changes that `git blame` would show by default
+    fn is_dirty(repo: &git2::Repository, rel_path: &std::path::Path) -> bool {
+        if repo.is_bare() {
+            return false;
+        }
+        let Ok(status) = repo.status_file(rel_path) else {
+            return false;
+        };
+        status.intersects(
+            git2::Status::INDEX_NEW
+                | git2::Status::INDEX_MODIFIED
+                | git2::Status::INDEX_RENAMED
+                | git2::Status::INDEX_TYPECHANGE
+                | git2::Status::WT_MODIFIED
+                | git2::Status::WT_RENAMED
+                | git2::Status::WT_TYPECHANGE,
+        )
+    }
+}
+
+pub(crate) fn to_repo_relative(
     cwd: &std::path::Path,
     path: &std::path::Path,
     repo: &git2::Repository,
@@ -183,6 +1001,121 @@ fn to_repo_relative(
     Ok(rel_path.to_owned())
 }
 
+/// Resolve `-L` arguments into sorted, inclusive line ranges
+///
+/// An empty list means the whole file.
+fn resolve_ranges(
+    line_ranges: &[crate::line_range::LineRange],
+    file: &str,
+    syntax: &syntect::parsing::SyntaxReference,
+    syntax_set: &syntect::parsing::SyntaxSet,
+) -> anyhow::Result<Vec<(usize, usize)>> {
+    let lines = file.lines().collect::<Vec<_>>();
+    let mut ranges = Vec::new();
+    let mut prior_end = 0;
+    for line_range in line_ranges {
+        let range = line_range.resolve(&lines, prior_end, syntax, syntax_set)?;
+        prior_end = range.1;
+        ranges.push(range);
+    }
+    ranges.sort_unstable();
+    // Like `git blame`, overlapping and adjacent ranges are merged
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, prior_end)) if start <= *prior_end + 1 => *prior_end = (*prior_end).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    Ok(merged)
+}
+
+/// Commits to skip over when assigning blame
+fn ignored_revs(
+    repo: &git2::Repository,
+    config: &Config,
+    args: &crate::args::Args,
+) -> anyhow::Result<std::collections::HashSet<git2::Oid>> {
+    let mut files = Vec::new();
+    if let Some(path) = config.get(&IGNORE_REVS_FILE) {
+        // Like git, this is relative to the top-level of the repo
+        let workdir = repo.workdir().unwrap_or_else(|| repo.path());
+        files.push(workdir.join(path));
+    }
+    for path in &args.ignore_revs_file {
+        if path.as_os_str().is_empty() {
+            files.clear();
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut revs = Vec::new();
+    for path in &files {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read ignored revisions from {}", path.display()))?;
+        for line in content.lines() {
+            let line = line.split_once('#').map(|(rev, _)| rev).unwrap_or(line);
+            let line = line.trim();
+            if !line.is_empty() {
+                revs.push(line.to_owned());
+            }
+        }
+    }
+    revs.extend(args.ignore_rev.iter().cloned());
+
+    let mut ignored = std::collections::HashSet::new();
+    for rev in revs {
+        let commit = peel_to_commit(repo, &rev)
+            .with_context(|| format!("Could not ignore revision `{rev}`"))?;
+        ignored.insert(commit.id());
+    }
+    Ok(ignored)
+}
+
+/// Prefix for re-attributed lines, matching `git blame`
+fn hunk_mark(hunk: &crate::hunks::Hunk, mark_ignored: bool, mark_unblamable: bool) -> &'static str {
+    if hunk.unblamable() && mark_unblamable {
+        "*"
+    } else if hunk.ignored() && mark_ignored {
+        "?"
+    } else {
+        ""
+    }
+}
+
+fn peel_to_commit<'r>(repo: &'r git2::Repository, rev: &str) -> anyhow::Result<git2::Commit<'r>> {
+    let rev_obj = repo.revparse_single(rev)?;
+    let rev_commit = rev_obj.peel_to_commit().map_err(|_| {
+        anyhow::format_err!(
+            "Unsupported rev `{}` ({})",
+            rev,
+            rev_obj.kind().map(|k| k.str()).unwrap_or("unknown")
+        )
+    })?;
+    Ok(rev_commit)
+}
+
+/// Where `rel_path` in `HEAD` was at `rev`
+fn follow_path(
+    repo: &git2::Repository,
+    rev: &str,
+    rel_path: &std::path::Path,
+) -> anyhow::Result<std::path::PathBuf> {
+    let head = peel_to_commit(repo, "HEAD")?;
+    let rev = peel_to_commit(repo, rev)?;
+    crate::follow::path_at(repo, head.id(), rev.id(), rel_path)
+}
+
+/// Split `OLD..NEW`, defaulting `NEW` to `HEAD` like `git blame --reverse`
+fn split_range(range: &str) -> (&str, &str) {
+    match range.split_once("..") {
+        Some((old, "")) => (old, "HEAD"),
+        Some((old, new)) => (old, new),
+        None => (range, "HEAD"),
+    }
+}
+
 fn read_file(
     repo: &git2::Repository,
     rev: &str,
@@ -209,7 +1142,7 @@ fn read_file(
     Ok(file.to_owned())
 }
 
-fn convert_file(buffer: &[u8], path: &std::path::Path) -> anyhow::Result<String> {
+pub(crate) fn convert_file(buffer: &[u8], path: &std::path::Path) -> anyhow::Result<String> {
     let content_type = content_inspector::inspect(buffer);
 
     let buffer = match content_type {
@@ -252,68 +1185,150 @@ fn convert_file(buffer: &[u8], path: &std::path::Path) -> anyhow::Result<String>
     Ok(buffer)
 }
 
+#[derive(Default)]
 pub(crate) struct Annotations {
     notes: std::collections::HashMap<git2::Oid, Annotation>,
 }
 
 impl Annotations {
-    pub(crate) fn new(repo: &git2::Repository, blame: &git2::Blame<'_>) -> Self {
-        let mut notes = std::collections::HashMap::new();
-        for hunk in blame.iter() {
-            let id = hunk.orig_commit_id();
-            notes.entry(id).or_insert_with(|| Annotation::new(repo, id));
+    /// Describe each commit in `hunks`, with the zero id being for `uncommitted` changes
+    pub(crate) fn new(
+        repo: &git2::Repository,
+        hunks: &Hunks,
+        uncommitted: Option<Uncommitted>,
+    ) -> Self {
+        let mut annotations = Annotations::default();
+        for hunk in hunks.iter() {
+            annotations.add(repo, hunk.commit_id(), uncommitted);
         }
+        annotations
+    }
 
-        Annotations { notes }
+    /// Describe `id`, if it isn't already
+    pub(crate) fn add(
+        &mut self,
+        repo: &git2::Repository,
+        id: git2::Oid,
+        uncommitted: Option<Uncommitted>,
+    ) {
+        self.notes.entry(id).or_insert_with(|| {
+            if id.is_zero() {
+                Annotation::uncommitted(uncommitted.unwrap_or(Uncommitted::Worktree))
+            } else {
+                Annotation::new(repo, id)
+            }
+        });
     }
 
+    /// Name commits in `style` and find the merge each was brought in on the way to `head`
+    ///
+    /// Commits that can't be named in `style` fall back to being named relative to `head`.
     pub(crate) fn relative_origin(
         &mut self,
         repo: &git2::Repository,
         head: &str,
+        style: crate::origin::OriginStyle,
+        pull_requests: &crate::merge::PullRequests,
     ) -> anyhow::Result<()> {
         let mut queue = self
             .notes
-            .keys()
-            .copied()
+            .iter()
+            .filter(|(_, note)| note.relative.is_none())
+            .map(|(id, _)| *id)
             .collect::<std::collections::HashSet<_>>();
+        if queue.is_empty() {
+            return Ok(());
+        }
+        let named = queue.clone();
 
-        let rev_obj = repo.revparse_single(head)?;
-        let rev_commit = rev_obj.peel_to_commit().map_err(|_| {
-            anyhow::format_err!(
-                "Unsupported rev `{}` ({})",
-                head,
-                rev_obj.kind().map(|k| k.str()).unwrap_or("unknown")
-            )
-        })?;
-
-        let (head, offset) = split_revset(head);
-
-        let mut revwalk = repo.revwalk()?;
-        revwalk.simplify_first_parent()?;
-        // If just walking first parents, shouldn't really need to sort
-        revwalk.set_sorting(git2::Sort::NONE)?;
-        revwalk.push(rev_commit.id())?;
-        for (i, id) in revwalk.enumerate() {
-            let i = i + offset;
-            let id = id?;
-            let relative = if i == 0 {
-                head.to_owned()
-            } else {
-                format!("{head}~{i}")
-            };
-            self.notes
-                .entry(id)
-                .or_insert_with(|| Annotation::new(repo, id))
-                .relative = Some(relative);
+        for entry in first_parent_history(repo, head)? {
+            let (relative, id) = entry?;
+            let commit = repo.find_commit(id)?;
+            let message = String::from_utf8_lossy(commit.message_bytes());
+            let pull_request = pull_requests.find(&message);
+            // Leave alone commits named by an earlier call, possibly with a different style
+            if queue.remove(&id) {
+                if let Some(note) = self.notes.get_mut(&id) {
+                    note.relative = Some(relative);
+                    note.pull_request.clone_from(&pull_request);
+                }
+            }
+
+            // Without `dive.firstParent`, lines can come from the branches being merged
+            if 1 < commit.parent_count() {
+                for merged in crate::merge::merged_by(repo, &commit)? {
+                    let merged = merged?;
+                    if queue.remove(&merged) {
+                        if let Some(note) = self.notes.get_mut(&merged) {
+                            note.pull_request.clone_from(&pull_request);
+                        }
+                        if queue.is_empty() {
+                            break;
+                        }
+                    }
+                }
+            }
 
-            queue.remove(&id);
             if queue.is_empty() {
                 break;
             }
         }
+
+        match style {
+            crate::origin::OriginStyle::Relative => {}
+            crate::origin::OriginStyle::Describe | crate::origin::OriginStyle::NameRev => {
+                for (id, name) in crate::origin::name_revs(repo, style, &named)? {
+                    if let Some(note) = self.notes.get_mut(&id) {
+                        note.relative = Some(name);
+                    }
+                }
+            }
+            crate::origin::OriginStyle::Sha => {
+                for id in &named {
+                    if let Some(note) = self.notes.get_mut(id) {
+                        note.relative = None;
+                    }
+                }
+            }
+        }
         Ok(())
     }
+
+    pub(crate) fn get(&self, id: git2::Oid) -> Option<&Annotation> {
+        self.notes.get(&id)
+    }
+}
+
+/// Walk the first parents of `head`, naming each commit relative to it, like `HEAD~2`
+pub(crate) fn first_parent_history<'r>(
+    repo: &'r git2::Repository,
+    head: &'r str,
+) -> anyhow::Result<impl Iterator<Item = anyhow::Result<(String, git2::Oid)>> + 'r> {
+    let rev_commit = peel_to_commit(repo, head)?;
+
+    let (head, offset) = split_revset(head);
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.simplify_first_parent()?;
+    // If just walking first parents, shouldn't really need to sort
+    revwalk.set_sorting(git2::Sort::NONE)?;
+    revwalk.push(rev_commit.id())?;
+    let history = revwalk.enumerate().map(move |(i, id)| {
+        let i = i + offset;
+        let relative = if i == 0 {
+            head.to_owned()
+        } else {
+            format!("{head}~{i}")
+        };
+        Ok((relative, id?))
+    });
+    Ok(history)
+}
+
+/// Name the first parent of `rev`, like `HEAD~3` for `HEAD~2`
+pub(crate) fn parent_rev(rev: &str) -> String {
+    let (base, offset) = split_revset(rev);
+    format!("{base}~{}", offset + 1)
 }
 
 fn split_revset(mut head: &str) -> (&str, usize) {
@@ -335,31 +1350,115 @@ fn split_revset(mut head: &str) -> (&str, usize) {
 pub(crate) struct Annotation {
     short: String,
     relative: Option<String>,
+    /// From the first-parent commit that brought this one in
+    pull_request: Option<String>,
+    author: Person,
+    committer: Person,
+    summary: String,
 }
 
 impl Annotation {
     pub(crate) fn new(repo: &git2::Repository, id: git2::Oid) -> Self {
-        let obj = repo.find_object(id, None).expect("blame has valid ids");
-        let short = obj
+        let commit = repo.find_commit(id).expect("blame has valid ids");
+        let short = commit
+            .as_object()
             .short_id()
             .unwrap_or_else(|e| panic!("unknown failure for short_id for {id}: {e}"))
             .as_str()
             .expect("short_id is always valid UTF-8")
             .to_owned();
+        let author = Person::new(&commit.author());
+        let committer = Person::new(&commit.committer());
+        let summary =
+            String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned();
         Self {
             short,
             relative: None,
+            pull_request: None,
+            author,
+            committer,
+            summary,
         }
     }
 
+    /// Stand-in for a commit, like `git blame` does for uncommitted changes
+    pub(crate) fn uncommitted(uncommitted: Uncommitted) -> Self {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let person = Person {
+            name: "Not Committed Yet".to_owned(),
+            email: "not.committed.yet".to_owned(),
+            time: git2::Time::new(now, 0),
+        };
+        Self {
+            short: git2::Oid::zero().to_string()[..7].to_owned(),
+            relative: Some(uncommitted.label().to_owned()),
+            pull_request: None,
+            author: person.clone(),
+            committer: person,
+            summary: format!("Version of file from {}", uncommitted.description()),
+        }
+    }
+
+    pub(crate) fn short(&self) -> &str {
+        self.short.as_str()
+    }
+
     pub(crate) fn origin(&self) -> &str {
         self.relative.as_deref().unwrap_or(self.short.as_str())
     }
+
+    /// Pull or merge request number, like `#123` or `!45`
+    pub(crate) fn pull_request(&self) -> Option<&str> {
+        self.pull_request.as_deref()
+    }
+
+    pub(crate) fn author(&self) -> &Person {
+        &self.author
+    }
+
+    pub(crate) fn committer(&self) -> &Person {
+        &self.committer
+    }
+
+    pub(crate) fn summary(&self) -> &str {
+        self.summary.as_str()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Person {
+    name: String,
+    email: String,
+    time: git2::Time,
+}
+
+impl Person {
+    fn new(signature: &git2::Signature<'_>) -> Self {
+        Self {
+            name: String::from_utf8_lossy(signature.name_bytes()).into_owned(),
+            email: String::from_utf8_lossy(signature.email_bytes()).into_owned(),
+            time: signature.when(),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub(crate) fn email(&self) -> &str {
+        self.email.as_str()
+    }
+
+    pub(crate) fn time(&self) -> git2::Time {
+        self.time
+    }
 }
 
 pub(crate) struct Highlighter<'a> {
     highlighter: Option<syntect::easy::HighlightLines<'a>>,
-    theme: &'a syntect::highlighting::Theme,
 }
 
 impl<'a> Highlighter<'a> {
@@ -368,62 +1467,27 @@ impl<'a> Highlighter<'a> {
         theme: &'a syntect::highlighting::Theme,
     ) -> Self {
         let highlighter = Some(syntect::easy::HighlightLines::new(syntax, theme));
-        Self { highlighter, theme }
+        Self { highlighter }
     }
 
     pub(crate) fn disabled() -> Self {
-        static THEME: syntect::highlighting::Theme = syntect::highlighting::Theme {
-            name: None,
-            author: None,
-            settings: syntect::highlighting::ThemeSettings {
-                foreground: None,
-                background: None,
-                caret: None,
-                line_highlight: None,
-                misspelling: None,
-                minimap_border: None,
-                accent: None,
-                popup_css: None,
-                phantom_css: None,
-                bracket_contents_foreground: None,
-                bracket_contents_options: None,
-                brackets_foreground: None,
-                brackets_background: None,
-                brackets_options: None,
-                tags_foreground: None,
-                tags_options: None,
-                highlight: None,
-                find_highlight: None,
-                find_highlight_foreground: None,
-                gutter: None,
-                gutter_foreground: None,
-                selection: None,
-                selection_foreground: None,
-                selection_border: None,
-                inactive_selection: None,
-                inactive_selection_foreground: None,
-                guide: None,
-                active_guide: None,
-                stack_guide: None,
-                shadow: None,
-            },
-            scopes: Vec::new(),
-        };
-        let highlighter = None;
-        Self {
-            highlighter,
-            theme: &THEME,
-        }
+        Self { highlighter: None }
     }
 
-    fn theme(&self) -> &syntect::highlighting::Theme {
-        self.theme
+    pub(crate) fn highlight_line(
+        &mut self,
+        line: &str,
+        syntax_set: &syntect::parsing::SyntaxSet,
+    ) -> anyhow::Result<String> {
+        self.highlight_marked(line, syntax_set, &[])
     }
 
-    pub(crate) fn highlight_line(
+    /// Highlight `line`, layering the style of each mark over its byte range
+    pub(crate) fn highlight_marked(
         &mut self,
         line: &str,
         syntax_set: &syntect::parsing::SyntaxSet,
+        marks: &[(std::ops::Range<usize>, anstyle::Style)],
     ) -> anyhow::Result<String> {
         if let Some(highlighter) = &mut self.highlighter {
             // skip syntax highlighting on long lines
@@ -435,16 +1499,43 @@ impl<'a> Highlighter<'a> {
             }
 
             let mut escaped = String::new();
-            for (style, region) in ranges {
+            let mut offset = 0;
+            for (style, mut region) in ranges {
                 use std::fmt::Write;
                 let style = body_style(style);
-                let _ = write!(
-                    &mut escaped,
-                    "{}{}{}",
-                    style.render(),
-                    region,
-                    anstyle::Reset.render()
-                );
+                // Split regions where marks start and end
+                while !region.is_empty() {
+                    let mark = marks.iter().find(|(range, _)| range.contains(&offset));
+                    let end = match mark {
+                        Some((range, _)) => range.end,
+                        None => marks
+                            .iter()
+                            .map(|(range, _)| range.start)
+                            .filter(|start| offset < *start)
+                            .min()
+                            .unwrap_or(usize::MAX),
+                    };
+                    let mut len = region.len().min(end - offset);
+                    while !region.is_char_boundary(len) {
+                        len += 1;
+                    }
+                    let (piece, rest) = region.split_at(len);
+                    let style = match mark {
+                        Some((_, mark)) => style
+                            .bg_color(mark.get_bg_color())
+                            .effects(style.get_effects() | mark.get_effects()),
+                        None => style,
+                    };
+                    let _ = write!(
+                        &mut escaped,
+                        "{}{}{}",
+                        style.render(),
+                        piece,
+                        anstyle::Reset.render()
+                    );
+                    region = rest;
+                    offset += len;
+                }
             }
             Ok(escaped)
         } else {
@@ -479,7 +1570,7 @@ fn body_style(style: syntect::highlighting::Style) -> anstyle::Style {
     output
 }
 
-fn gutter_style(theme: &syntect::highlighting::Theme) -> anstyle::Style {
+pub(crate) fn gutter_style(theme: &syntect::highlighting::Theme) -> anstyle::Style {
     const DEFAULT_GUTTER_COLOR: u8 = 238;
 
     // If the theme provides a gutter foreground color, use it.
@@ -487,11 +1578,89 @@ fn gutter_style(theme: &syntect::highlighting::Theme) -> anstyle::Style {
         .settings
         .gutter_foreground
         .map(crate::assets::to_anstyle_color)
-        .unwrap_or_else(|| Some(anstyle::Ansi256Color(DEFAULT_GUTTER_COLOR).into()));
+        .unwrap_or_else(|| {
+            Some(crate::color_depth::downsample(
+                anstyle::Ansi256Color(DEFAULT_GUTTER_COLOR).into(),
+            ))
+        });
 
     anstyle::Style::new().fg_color(fg_color)
 }
 
+/// Keep room for code when showing where code was moved from
+const MAX_PATH_WIDTH: usize = 30;
+
+/// The theme to use and why
+pub(crate) struct ResolvedTheme {
+    pub(crate) name: String,
+    pub(crate) reason: String,
+}
+
+/// Look up `dive.theme`, picking between `dive.theme.light` and `dive.theme.dark` for `auto`
+///
+/// Only detects the terminal's background when `detect`, since that can mean querying it.
+pub(crate) fn resolve_theme(config: &Config, detect: bool) -> ResolvedTheme {
+    use crate::background::Background;
+    use crate::background::Detection;
+
+    let name = config.get(&THEME);
+    if name != THEME_AUTO {
+        return ResolvedTheme {
+            name,
+            reason: "set by `dive.theme`".to_owned(),
+        };
+    }
+
+    let (background, detection) = if detect {
+        crate::background::detect()
+    } else {
+        (Background::Dark, Detection::Guess)
+    };
+    let name = match background {
+        Background::Light => config.get(&THEME_LIGHT),
+        Background::Dark => config.get(&THEME_DARK),
+    };
+    let reason = match detection {
+        Detection::Query => format!("the terminal reported a {background} background"),
+        Detection::ColorFgBg => format!("`COLORFGBG` is for a {background} background"),
+        Detection::Guess => format!("assuming a {background} background"),
+    };
+    ResolvedTheme { name, reason }
+}
+
 const THEME_DEFAULT: &str = "Monokai Extended";
+const THEME_AUTO: &str = "auto";
 pub(crate) const THEME: DefaultField<String> =
-    RawField::<String>::new("dive.theme").default_value(|| THEME_DEFAULT.to_owned());
+    RawField::<String>::new("dive.theme").default_value(|| THEME_AUTO.to_owned());
+pub(crate) const THEME_LIGHT: DefaultField<String> = RawField::<String>::new("dive.theme.light")
+    .default_value(|| "Monokai Extended Light".to_owned());
+pub(crate) const THEME_DARK: DefaultField<String> =
+    RawField::<String>::new("dive.theme.dark").default_value(|| THEME_DEFAULT.to_owned());
+
+pub(crate) const IGNORE_REVS_FILE: RawField<std::path::PathBuf> =
+    RawField::<std::path::PathBuf>::new("blame.ignoreRevsFile");
+pub(crate) const MARK_IGNORED_LINES: DefaultField<bool> =
+    RawField::<bool>::new("blame.markIgnoredLines").default();
+pub(crate) const MARK_UNBLAMABLE_LINES: DefaultField<bool> =
+    RawField::<bool>::new("blame.markUnblamableLines").default();
+pub(crate) const INTERACTIVE: DefaultField<bool> =
+    RawField::<bool>::new("dive.interactive").default();
+
+pub(crate) const GUTTER: DefaultField<crate::gutter::Gutter> =
+    RawField::<crate::gutter::Gutter>::new("dive.gutter").default();
+pub(crate) const COLOR_BY: DefaultField<crate::palette::ColorBy> =
+    RawField::<crate::palette::ColorBy>::new("dive.colorBy").default();
+pub(crate) const ORIGIN_STYLE: DefaultField<crate::origin::OriginStyle> =
+    RawField::<crate::origin::OriginStyle>::new("dive.originStyle").default();
+pub(crate) const WRAP: DefaultField<Overflow> = RawField::<Overflow>::new("dive.wrap").default();
+
+pub(crate) const FIRST_PARENT: DefaultField<bool> =
+    RawField::<bool>::new("dive.firstParent").default_value(|| true);
+pub(crate) const IGNORE_WHITESPACE: DefaultField<bool> =
+    RawField::<bool>::new("dive.ignoreWhitespace").default_value(|| true);
+pub(crate) const DETECT_MOVES: DefaultField<bool> =
+    RawField::<bool>::new("dive.detectMoves").default_value(|| true);
+pub(crate) const DETECT_COPIES: DefaultField<i32> =
+    RawField::<i32>::new("dive.detectCopies").default_value(|| 3);
+pub(crate) const PULL_REQUEST_PATTERN: RawField<String> =
+    RawField::<String>::new("dive.pullRequestPattern");
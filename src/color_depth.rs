@@ -0,0 +1,256 @@
+//! Downsampling theme colors for terminals without truecolor
+
+use crate::git2_config::DefaultField;
+use crate::git2_config::RawField;
+
+/// How many colors the terminal supports, from `dive.colorDepth`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub(crate) enum ColorDepth {
+    /// Detect from `COLORTERM` and `TERM`
+    #[default]
+    Auto,
+    /// 24-bit RGB colors
+    TrueColor,
+    /// The xterm 256-color palette
+    Ansi256,
+    /// The 8 basic colors and their bright variants
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::TrueColor => "truecolor",
+            Self::Ansi256 => "256",
+            Self::Ansi16 => "16",
+        }
+    }
+
+    /// Resolve [`ColorDepth::Auto`] from the environment
+    pub(crate) fn resolve(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").ok();
+        Self::detect(&colorterm, term.as_deref())
+    }
+
+    fn detect(colorterm: &str, term: Option<&str>) -> Self {
+        if matches!(colorterm, "truecolor" | "24bit") {
+            return Self::TrueColor;
+        }
+        match term {
+            // Windows terminals don't set `TERM` and have supported truecolor since Windows 10
+            None => Self::TrueColor,
+            Some(term) if term.ends_with("256color") => Self::Ansi256,
+            Some("linux" | "ansi" | "cons25" | "screen" | "tmux") => Self::Ansi16,
+            Some(term) if term.starts_with("vt") => Self::Ansi16,
+            // Nearly every terminal emulator supports at least 256 colors
+            Some(_) => Self::Ansi256,
+        }
+    }
+
+    /// Use this depth for all colors from themes
+    pub(crate) fn write_global(self) {
+        let depth = match self.resolve() {
+            Self::Auto | Self::TrueColor => 0,
+            Self::Ansi256 => 1,
+            Self::Ansi16 => 2,
+        };
+        GLOBAL.store(depth, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn global() -> Self {
+        match GLOBAL.load(std::sync::atomic::Ordering::Relaxed) {
+            1 => Self::Ansi256,
+            2 => Self::Ansi16,
+            _ => Self::TrueColor,
+        }
+    }
+}
+
+impl std::fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl std::str::FromStr for ColorDepth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "truecolor" | "24bit" => Ok(Self::TrueColor),
+            "256" => Ok(Self::Ansi256),
+            "16" => Ok(Self::Ansi16),
+            _ => Err(anyhow::format_err!(
+                "unsupported colorDepth value: `{s}`, expected one of `truecolor`, `256`, `16`, `auto`"
+            )),
+        }
+    }
+}
+
+impl crate::git2_config::Parseable for ColorDepth {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        <Self as std::str::FromStr>::from_str(s)
+    }
+}
+
+pub(crate) const COLOR_DEPTH: DefaultField<ColorDepth> =
+    RawField::<ColorDepth>::new("dive.colorDepth").default();
+
+static GLOBAL: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(0);
+
+/// Replace `color` with the nearest one the terminal supports, per [`ColorDepth::write_global`]
+pub(crate) fn downsample(color: anstyle::Color) -> anstyle::Color {
+    downsample_to(color, ColorDepth::global())
+}
+
+fn downsample_to(color: anstyle::Color, depth: ColorDepth) -> anstyle::Color {
+    match (color, depth) {
+        (anstyle::Color::Rgb(rgb), ColorDepth::Ansi256) => {
+            anstyle::Ansi256Color(nearest_256(rgb)).into()
+        }
+        (anstyle::Color::Rgb(rgb), ColorDepth::Ansi16) => nearest_16(rgb),
+        (anstyle::Color::Ansi256(anstyle::Ansi256Color(n)), ColorDepth::Ansi16) => {
+            nearest_16(palette_rgb(n))
+        }
+        _ => color,
+    }
+}
+
+/// Nearest color in the 6x6x6 cube or the grayscale ramp of the 256-color palette
+fn nearest_256(rgb: anstyle::RgbColor) -> u8 {
+    let cube = |c: u8| -> u8 {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| level.abs_diff(c))
+            .map(|(i, _)| i as u8)
+            .expect("levels are non-empty")
+    };
+    let (r, g, b) = (cube(rgb.0), cube(rgb.1), cube(rgb.2));
+    let cube_index = 16 + 36 * r + 6 * g + b;
+
+    let average = (u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_index = 232 + gray_step;
+
+    if distance(rgb, palette_rgb(gray_index)) < distance(rgb, palette_rgb(cube_index)) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+fn nearest_16(rgb: anstyle::RgbColor) -> anstyle::Color {
+    let index = (0..16)
+        .min_by_key(|i| distance(rgb, palette_rgb(*i)))
+        .expect("palette is non-empty");
+    anstyle::Ansi256Color(index)
+        .into_ansi()
+        .expect("the first 16 are ANSI colors")
+        .into()
+}
+
+/// Colors of the 256-color palette, using xterm's defaults for the first 16
+fn palette_rgb(index: u8) -> anstyle::RgbColor {
+    match index {
+        0..=15 => {
+            let (r, g, b) = ANSI_16[usize::from(index)];
+            anstyle::RgbColor(r, g, b)
+        }
+        16..=231 => {
+            let i = index - 16;
+            anstyle::RgbColor(
+                CUBE_LEVELS[usize::from(i / 36)],
+                CUBE_LEVELS[usize::from(i / 6 % 6)],
+                CUBE_LEVELS[usize::from(i % 6)],
+            )
+        }
+        232..=255 => {
+            let level = 8 + 10 * (index - 232);
+            anstyle::RgbColor(level, level, level)
+        }
+    }
+}
+
+/// Squared distance, weighted for how sensitive eyes are to each channel
+fn distance(a: anstyle::RgbColor, b: anstyle::RgbColor) -> u32 {
+    let channel = |x: u8, y: u8, weight: u32| weight * u32::from(x.abs_diff(y)).pow(2);
+    channel(a.0, b.0, 3) + channel(a.1, b.1, 4) + channel(a.2, b.2, 2)
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+const ANSI_16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            ColorDepth::detect("truecolor", Some("xterm-256color")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            ColorDepth::detect("", Some("tmux-256color")),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(ColorDepth::detect("", Some("linux")), ColorDepth::Ansi16);
+        assert_eq!(ColorDepth::detect("", None), ColorDepth::TrueColor);
+    }
+
+    #[test]
+    fn quantize() {
+        // Monokai's foreground is nearly white
+        let fg = anstyle::RgbColor(248, 248, 242).into();
+        assert_eq!(
+            downsample_to(fg, ColorDepth::Ansi256),
+            anstyle::Ansi256Color(231).into()
+        );
+        assert_eq!(
+            downsample_to(fg, ColorDepth::Ansi16),
+            anstyle::AnsiColor::BrightWhite.into()
+        );
+        // Monokai's keywords are pink
+        let keyword = anstyle::RgbColor(249, 38, 114).into();
+        assert_eq!(
+            downsample_to(keyword, ColorDepth::Ansi256),
+            anstyle::Ansi256Color(197).into()
+        );
+        // Monokai's comments are a muted gray
+        let gray = anstyle::RgbColor(117, 113, 94).into();
+        assert_eq!(
+            downsample_to(gray, ColorDepth::Ansi16),
+            anstyle::AnsiColor::BrightBlack.into()
+        );
+        assert_eq!(
+            downsample_to(anstyle::Ansi256Color(238).into(), ColorDepth::Ansi16),
+            anstyle::AnsiColor::BrightBlack.into()
+        );
+        assert_eq!(downsample_to(fg, ColorDepth::TrueColor), fg);
+    }
+}
@@ -2,6 +2,7 @@ use proc_exit::WithCodeResultExt;
 
 use crate::git2_config::Config;
 use crate::git2_config::ReflectField;
+use crate::git2_config::ReflectWith;
 
 pub(crate) fn dump_config(
     output_path: &std::path::Path,
@@ -14,8 +15,42 @@ pub(crate) fn dump_config(
     let output = config.dump([
         &crate::git2_config::COLOR_UI as &dyn ReflectField,
         &crate::git2_config::PAGER as &dyn ReflectField,
+        &ReflectWith::new(
+            crate::blame::IGNORE_REVS_FILE,
+            crate::git2_config::display_path,
+        ) as &dyn ReflectField,
+        &crate::blame::MARK_IGNORED_LINES as &dyn ReflectField,
+        &crate::blame::MARK_UNBLAMABLE_LINES as &dyn ReflectField,
         &crate::blame::THEME as &dyn ReflectField,
+        &crate::color_depth::COLOR_DEPTH as &dyn ReflectField,
+        &crate::blame::GUTTER as &dyn ReflectField,
+        &crate::blame::COLOR_BY as &dyn ReflectField,
+        &crate::blame::ORIGIN_STYLE as &dyn ReflectField,
+        &ReflectWith::new(
+            crate::blame::PULL_REQUEST_PATTERN,
+            crate::git2_config::display_optional,
+        ) as &dyn ReflectField,
+        &crate::blame::WRAP as &dyn ReflectField,
+        &crate::blame::FIRST_PARENT as &dyn ReflectField,
+        &crate::blame::IGNORE_WHITESPACE as &dyn ReflectField,
+        &crate::blame::DETECT_MOVES as &dyn ReflectField,
+        &crate::blame::DETECT_COPIES as &dyn ReflectField,
+        &crate::blame::INTERACTIVE as &dyn ReflectField,
+        &ReflectWith::new(
+            crate::assets::SYNTAXES_DIR,
+            crate::git2_config::display_path,
+        ) as &dyn ReflectField,
+        &ReflectWith::new(crate::assets::THEMES_DIR, crate::git2_config::display_path)
+            as &dyn ReflectField,
+        &ReflectWith::new(
+            crate::syntax_mapping::SYNTAX_MAP,
+            crate::git2_config::display_list,
+        ) as &dyn ReflectField,
+        &crate::blame::THEME_LIGHT as &dyn ReflectField,
+        &crate::blame::THEME_DARK as &dyn ReflectField,
     ]);
+    let theme = crate::blame::resolve_theme(config, true);
+    let output = format!("{output}# Using theme `{}`: {}\n", theme.name, theme.reason);
 
     if output_path == std::path::Path::new("-") {
         use std::io::Write;
@@ -0,0 +1,432 @@
+/// Maps line numbers between two versions of a file
+///
+/// Line numbers are 1-based, like everywhere else in git.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LineMap {
+    hunks: Vec<LineHunk>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct LineHunk {
+    old_start: usize,
+    old_lines: usize,
+    new_start: usize,
+    new_lines: usize,
+}
+
+impl LineHunk {
+    /// First line removed, or the line after the insertion point for pure additions
+    fn old_first(&self) -> usize {
+        if self.old_lines == 0 {
+            self.old_start + 1
+        } else {
+            self.old_start
+        }
+    }
+
+    /// First line added, or the line after the removal point for pure deletions
+    fn new_first(&self) -> usize {
+        if self.new_lines == 0 {
+            self.new_start + 1
+        } else {
+            self.new_start
+        }
+    }
+
+    /// Whether this changes any of the new version's lines `start..=end`
+    fn touches(&self, start: usize, end: usize) -> bool {
+        if self.new_lines == 0 {
+            start <= self.new_start && self.new_start < end
+        } else {
+            self.new_start <= end && start < self.new_start + self.new_lines
+        }
+    }
+}
+
+impl LineMap {
+    pub(crate) fn from_buffers(old: &[u8], new: &[u8]) -> anyhow::Result<Self> {
+        let mut options = git2::DiffOptions::new();
+        options.context_lines(0);
+        let patch = git2::Patch::from_buffers(old, None, new, None, Some(&mut options))?;
+        let mut hunks = Vec::with_capacity(patch.num_hunks());
+        for i in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(i)?;
+            hunks.push(LineHunk {
+                old_start: hunk.old_start() as usize,
+                old_lines: hunk.old_lines() as usize,
+                new_start: hunk.new_start() as usize,
+                new_lines: hunk.new_lines() as usize,
+            });
+        }
+        Ok(Self { hunks })
+    }
+
+    /// Where `line` ended up in the new version, if it survived unchanged
+    pub(crate) fn old_to_new(&self, line: usize) -> Option<usize> {
+        let mut offset = 0_isize;
+        for hunk in &self.hunks {
+            let first = hunk.old_first();
+            if line < first {
+                break;
+            }
+            if line < first + hunk.old_lines {
+                return None;
+            }
+            offset += hunk.new_lines as isize - hunk.old_lines as isize;
+        }
+        Some(line.saturating_add_signed(offset))
+    }
+
+    /// Where `line` came from in the old version, if it was left unchanged
+    pub(crate) fn new_to_old(&self, line: usize) -> Option<usize> {
+        let mut offset = 0_isize;
+        for hunk in &self.hunks {
+            let first = hunk.new_first();
+            if line < first {
+                break;
+            }
+            if line < first + hunk.new_lines {
+                return None;
+            }
+            offset += hunk.old_lines as isize - hunk.new_lines as isize;
+        }
+        Some(line.saturating_add_signed(offset))
+    }
+
+    /// Where `line` most likely came from in the old version
+    ///
+    /// Lines within a change are paired up with the removed lines by position, like git's
+    /// fallback for `--ignore-rev`.  Lines without a counterpart, like most additions, have no
+    /// origin.
+    pub(crate) fn new_to_old_fuzzy(&self, line: usize) -> Option<usize> {
+        let mut offset = 0_isize;
+        for hunk in &self.hunks {
+            let first = hunk.new_first();
+            if line < first {
+                break;
+            }
+            if line < first + hunk.new_lines {
+                let index = line - first;
+                return (index < hunk.old_lines).then(|| hunk.old_first() + index);
+            }
+            offset += hunk.old_lines as isize - hunk.new_lines as isize;
+        }
+        Some(line.saturating_add_signed(offset))
+    }
+
+    /// Whether the change touched any of the lines `start..=end` of the new version
+    ///
+    /// Removing lines from between two lines of the range counts as touching it.
+    pub(crate) fn changes(&self, start: usize, end: usize) -> bool {
+        self.hunks.iter().any(|hunk| hunk.touches(start, end))
+    }
+
+    /// The lines of the old version that `start..=end` of the new version came from
+    ///
+    /// Changes touching the range pull in all of the lines they replaced.  Returns `None` when
+    /// every line in the range was added.
+    pub(crate) fn new_to_old_range(&self, start: usize, end: usize) -> Option<(usize, usize)> {
+        let unchanged = (start..=end).filter_map(|line| self.new_to_old_fuzzy(line));
+        let replaced = self
+            .hunks
+            .iter()
+            .filter(|hunk| hunk.touches(start, end))
+            .flat_map(|hunk| hunk.old_first()..hunk.old_first() + hunk.old_lines);
+        let (mut old_start, mut old_end) = (usize::MAX, 0);
+        for line in unchanged.chain(replaced) {
+            old_start = old_start.min(line);
+            old_end = old_end.max(line);
+        }
+        (old_start <= old_end).then_some((old_start, old_end))
+    }
+}
+
+/// How a file changed in a commit, for showing like `git diff`
+pub(crate) struct FileChange {
+    old_path: std::path::PathBuf,
+    /// `None` when the commit added the file
+    old: Option<Vec<u8>>,
+    new_path: std::path::PathBuf,
+    new: Vec<u8>,
+}
+
+impl FileChange {
+    /// Compare `path` in `commit` against its first parent, following a rename
+    ///
+    /// Returns `None` when `path` isn't in `commit`.
+    pub(crate) fn new(
+        repo: &git2::Repository,
+        commit: &git2::Commit<'_>,
+        path: &std::path::Path,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(new) = crate::hunks::read_blob(repo, commit, path)? else {
+            return Ok(None);
+        };
+        let new = new.content().to_owned();
+        let old_path =
+            crate::follow::renamed_from(repo, commit, path)?.unwrap_or_else(|| path.to_owned());
+        let old = match commit.parent(0) {
+            Ok(parent) => crate::hunks::read_blob(repo, &parent, &old_path)?
+                .map(|blob| blob.content().to_owned()),
+            Err(_) => None,
+        };
+        Ok(Some(Self {
+            old_path,
+            old,
+            new_path: path.to_owned(),
+            new,
+        }))
+    }
+
+    /// Map lines between the old and new versions
+    pub(crate) fn line_map(&self) -> anyhow::Result<LineMap> {
+        LineMap::from_buffers(self.old.as_deref().unwrap_or_default(), &self.new)
+    }
+
+    /// The file before the commit, empty when the commit added it
+    pub(crate) fn old_content(&self) -> &[u8] {
+        self.old.as_deref().unwrap_or_default()
+    }
+
+    pub(crate) fn new_content(&self) -> &[u8] {
+        &self.new
+    }
+
+    /// What the file was called before the commit
+    pub(crate) fn old_path(&self) -> &std::path::Path {
+        &self.old_path
+    }
+
+    /// Whether the commit added the file
+    pub(crate) fn is_added(&self) -> bool {
+        self.old.is_none()
+    }
+
+    /// Whether the content changed, rather than just the name or nothing at all
+    pub(crate) fn is_modified(&self) -> bool {
+        self.old.as_deref() != Some(self.new.as_slice())
+    }
+
+    /// Render the diff, highlighted, limited to `selection`
+    pub(crate) fn render(
+        &self,
+        blamer: &crate::blame::Blamer<'_>,
+        selection: &Selection,
+    ) -> anyhow::Result<Vec<String>> {
+        let old = self.old.as_deref().unwrap_or_default();
+        let old_file = crate::blame::convert_file(old, &self.old_path)?;
+        let new_file = crate::blame::convert_file(&self.new, &self.new_path)?;
+        let old_lines = highlight(blamer, &self.old_path, &old_file)?;
+        let new_lines = highlight(blamer, &self.new_path, &new_file)?;
+
+        let mut options = git2::DiffOptions::new();
+        if let Selection::Lines(_) = selection {
+            // Everything is context, leaving it to the ranges to pick what to show
+            options.context_lines(old_lines.len().max(new_lines.len()) as u32);
+        } else {
+            options.context_lines(3);
+        }
+        let patch = git2::Patch::from_buffers(
+            old,
+            Some(&self.old_path),
+            &self.new,
+            Some(&self.new_path),
+            Some(&mut options),
+        )?;
+
+        let paint = |style: anstyle::Style, text: &str| {
+            if blamer.colored() {
+                format!("{}{text}{}", style.render(), anstyle::Reset.render())
+            } else {
+                text.to_owned()
+            }
+        };
+        let bold = anstyle::Style::new().effects(anstyle::Effects::BOLD);
+        let fg = |color: anstyle::AnsiColor| anstyle::Style::new().fg_color(Some(color.into()));
+        let render_line = |line: &git2::DiffLine<'_>| {
+            let (prefix, content) = match line.origin_value() {
+                git2::DiffLineType::Addition => (
+                    paint(fg(anstyle::AnsiColor::Green), "+"),
+                    line.new_lineno()
+                        .and_then(|n| new_lines.get(n as usize - 1)),
+                ),
+                git2::DiffLineType::Deletion => (
+                    paint(fg(anstyle::AnsiColor::Red), "-"),
+                    line.old_lineno()
+                        .and_then(|n| old_lines.get(n as usize - 1)),
+                ),
+                git2::DiffLineType::Context => (
+                    " ".to_owned(),
+                    line.new_lineno()
+                        .and_then(|n| new_lines.get(n as usize - 1)),
+                ),
+                _ => return None,
+            };
+            let content = content.map(|c| c.as_str()).unwrap_or_default();
+            Some(format!("{prefix}{content}"))
+        };
+
+        let old_name = if self.is_added() {
+            "/dev/null".to_owned()
+        } else {
+            format!("a/{}", self.old_path.display())
+        };
+        let mut rendered = vec![paint(
+            bold,
+            &format!(
+                "diff --git a/{} b/{}",
+                self.old_path.display(),
+                self.new_path.display()
+            ),
+        )];
+        if self.old_path != self.new_path {
+            rendered.push(paint(
+                bold,
+                &format!("rename from {}", self.old_path.display()),
+            ));
+            rendered.push(paint(
+                bold,
+                &format!("rename to {}", self.new_path.display()),
+            ));
+        }
+        if self.is_modified() {
+            rendered.push(paint(bold, &format!("--- {old_name}")));
+            rendered.push(paint(bold, &format!("+++ b/{}", self.new_path.display())));
+        }
+
+        if let Selection::Lines(ranges) = selection {
+            let mut lines = Vec::new();
+            for hunk_idx in 0..patch.num_hunks() {
+                for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+                    lines.push(patch.line_in_hunk(hunk_idx, line_idx)?);
+                }
+            }
+            for ((new_start, new_end), old_range) in ranges {
+                let (old_start, old_count) = old_range
+                    .map(|(start, end)| (start, end + 1 - start))
+                    .unwrap_or((0, 0));
+                let header = format!(
+                    "@@ -{old_start},{old_count} +{new_start},{} @@",
+                    new_end + 1 - new_start
+                );
+                rendered.push(paint(fg(anstyle::AnsiColor::Cyan), &header));
+                let in_new = |n: Option<u32>| {
+                    n.is_some_and(|n| (*new_start..=*new_end).contains(&(n as usize)))
+                };
+                let in_old = |n: Option<u32>| {
+                    n.zip(*old_range)
+                        .is_some_and(|(n, (start, end))| (start..=end).contains(&(n as usize)))
+                };
+                for line in &lines {
+                    let selected = match line.origin_value() {
+                        git2::DiffLineType::Deletion => in_old(line.old_lineno()),
+                        _ => in_new(line.new_lineno()),
+                    };
+                    if selected {
+                        rendered.extend(render_line(line));
+                    }
+                }
+            }
+            return Ok(rendered);
+        }
+
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_idx)?;
+            if let Selection::Hunks(ranges) = selection {
+                let new_start = hunk.new_start() as usize;
+                // Pure deletions still sit next to a line
+                let new_end = new_start + (hunk.new_lines() as usize).max(1);
+                if !ranges
+                    .iter()
+                    .any(|(start, end)| *start < new_end && new_start <= *end)
+                {
+                    continue;
+                }
+            }
+
+            let header = String::from_utf8_lossy(hunk.header());
+            rendered.push(paint(fg(anstyle::AnsiColor::Cyan), header.trim_end()));
+            for line_idx in 0..line_count {
+                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                rendered.extend(render_line(&line));
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+/// What parts of a [`FileChange`] to show
+pub(crate) enum Selection {
+    /// Every change, with some context
+    All,
+    /// Changes touching the new version's lines `start..=end`, with some context
+    Hunks(Vec<(usize, usize)>),
+    /// Just the new version's lines `start..=end`, along with the old version's lines they came
+    /// from, like `git log -L`
+    Lines(Vec<(Span, Option<Span>)>),
+}
+
+/// Inclusive range of 1-based line numbers
+pub(crate) type Span = (usize, usize);
+
+/// Quickly rule out commits that didn't touch `path`, without reading it
+pub(crate) fn is_unchanged(
+    commit: &git2::Commit<'_>,
+    path: &std::path::Path,
+) -> anyhow::Result<bool> {
+    let Ok(parent) = commit.parent(0) else {
+        return Ok(false);
+    };
+    let new = commit.tree()?.get_path(path).ok().map(|e| e.id());
+    let old = parent.tree()?.get_path(path).ok().map(|e| e.id());
+    Ok(new.is_some() && new == old)
+}
+
+/// Highlight every line of `file`, since highlighting depends on the lines before
+fn highlight(
+    blamer: &crate::blame::Blamer<'_>,
+    path: &std::path::Path,
+    file: &str,
+) -> anyhow::Result<Vec<String>> {
+    let syntax_set = blamer.syntax_set();
+    let mut highlighter = if blamer.colored() {
+        let syntax = blamer.find_syntax(path, file);
+        crate::blame::Highlighter::enabled(syntax, blamer.theme())
+    } else {
+        crate::blame::Highlighter::disabled()
+    };
+    let file = file.strip_prefix('\u{feff}').unwrap_or(file);
+    file.lines()
+        .map(|line| highlighter.highlight_line(line, syntax_set))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        let old = "a\nb\nc\nd\ne\n";
+        let new = "z\na\nb\nC\nd\nnew\ne\n";
+        let map = LineMap::from_buffers(old.as_bytes(), new.as_bytes()).unwrap();
+
+        // Only shifted
+        assert!(!map.changes(2, 3));
+        assert_eq!(map.new_to_old_range(2, 3), Some((1, 2)));
+        // Modified
+        assert!(map.changes(4, 4));
+        assert_eq!(map.new_to_old_range(3, 5), Some((2, 4)));
+        // Added
+        assert!(map.changes(1, 1));
+        assert_eq!(map.new_to_old_range(1, 1), None);
+        assert_eq!(map.new_to_old_range(6, 6), None);
+        assert_eq!(map.new_to_old_range(5, 7), Some((4, 5)));
+
+        let map = LineMap::from_buffers(old.as_bytes(), b"a\nb\nd\ne\n").unwrap();
+        // Removed from between the lines
+        assert!(map.changes(2, 3));
+        assert!(!map.changes(3, 4));
+        assert_eq!(map.new_to_old_range(2, 3), Some((2, 4)));
+    }
+}
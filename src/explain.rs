@@ -0,0 +1,225 @@
+//! Show the commit behind a line of a blame
+
+use anyhow::Context as _;
+use proc_exit::WithCodeResultExt;
+
+use crate::git_pager::Pager;
+use crate::git2_config::Config;
+
+/// A line in a file, like `src/main.rs:42`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Location {
+    pub(crate) path: std::path::PathBuf,
+    pub(crate) line: usize,
+}
+
+impl std::str::FromStr for Location {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, line) = s
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow::format_err!("expected `<file>:<line>`, got `{s}`"))?;
+        if path.is_empty() {
+            anyhow::bail!("expected `<file>:<line>`, got `{s}`");
+        }
+        let line = line
+            .parse::<usize>()
+            .ok()
+            .filter(|line| *line != 0)
+            .ok_or_else(|| anyhow::format_err!("invalid line number `{line}`"))?;
+        Ok(Self {
+            path: path.into(),
+            line,
+        })
+    }
+}
+
+pub(crate) fn explain(
+    location: &Location,
+    config: &mut Config,
+    args: &crate::args::Args,
+) -> proc_exit::ExitResult {
+    let colored_stdout =
+        anstream::AutoStream::choice(&std::io::stdout()) != anstream::ColorChoice::Never;
+
+    let cwd = std::env::current_dir().with_code(proc_exit::Code::FAILURE)?;
+    let repo = git2::Repository::discover(&cwd).with_code(proc_exit::Code::FAILURE)?;
+    config.add_repo(&repo);
+
+    let rel_path = crate::blame::to_repo_relative(&cwd, &location.path, &repo)
+        .with_code(proc_exit::Code::FAILURE)?;
+
+    let syntax_set = crate::assets::load_syntaxes(config);
+    let theme_set = crate::assets::load_themes(config);
+    let blamer =
+        crate::blame::Blamer::new(&repo, &syntax_set, &theme_set, config, args, colored_stdout)
+            .with_code(proc_exit::Code::FAILURE)?;
+    let blamed = blamer
+        .blame_args(args, &rel_path)
+        .with_code(proc_exit::Code::FAILURE)?;
+
+    let line = location.line;
+    let hunk = blamed
+        .hunk(line)
+        .ok_or_else(|| {
+            anyhow::format_err!(
+                "line {line} is past the end of {} ({} lines)",
+                blamed.path.display(),
+                blamed.line_count()
+            )
+        })
+        .with_code(proc_exit::Code::FAILURE)?;
+    let annotation = blamed
+        .annotation(line)
+        .expect("all blame hunks are annotated");
+    if hunk.commit_id().is_zero() {
+        return Err(anyhow::format_err!(
+            "line {line} of {} has not been committed yet",
+            blamed.path.display()
+        ))
+        .with_code(proc_exit::Code::FAILURE);
+    }
+
+    let mut explained = String::new();
+    write_commit(&mut explained, &blamer, hunk, annotation).with_code(proc_exit::Code::FAILURE)?;
+    explained.push('\n');
+    write_diff(&mut explained, &blamer, hunk).with_code(proc_exit::Code::FAILURE)?;
+
+    let pager = config.get(&crate::git2_config::PAGER);
+    let mut pager = Pager::stdout(&pager);
+    let mut pager = pager.start();
+    let pager = pager.as_writer().with_code(proc_exit::Code::FAILURE)?;
+    let _ = write!(pager, "{explained}");
+
+    Ok(())
+}
+
+/// Describe the commit like `git show`, naming it relative to the blamed revision
+fn write_commit(
+    out: &mut String,
+    blamer: &crate::blame::Blamer<'_>,
+    hunk: &crate::hunks::Hunk,
+    annotation: &crate::blame::Annotation,
+) -> anyhow::Result<()> {
+    use std::fmt::Write as _;
+
+    let repo = blamer.repo();
+    let commit = repo.find_commit(hunk.commit_id())?;
+    let (header, reset) = if blamer.colored() {
+        (
+            anstyle::AnsiColor::Yellow.render_fg().to_string(),
+            anstyle::Reset.render().to_string(),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+
+    let _ = writeln!(
+        out,
+        "{header}commit {} ({}){reset}",
+        commit.id(),
+        annotation.origin()
+    );
+    if 1 < commit.parent_count() {
+        let parents = commit
+            .parent_ids()
+            .map(|id| {
+                repo.find_object(id, None)
+                    .and_then(|o| o.short_id())
+                    .ok()
+                    .and_then(|s| s.as_str().map(|s| s.to_owned()))
+                    .unwrap_or_else(|| id.to_string())
+            })
+            .collect::<Vec<_>>();
+        let _ = writeln!(out, "Merge: {}", parents.join(" "));
+    }
+    let author = annotation.author();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let _ = writeln!(out, "Author: {} <{}>", author.name(), author.email());
+    let _ = writeln!(
+        out,
+        "Date:   {} ({})",
+        crate::gutter::format_date(author.time()),
+        crate::gutter::format_age(author.time(), now)
+    );
+    if let Some(pull_request) = annotation.pull_request() {
+        let _ = writeln!(out, "Pull-Request: {pull_request}");
+    }
+
+    let message = String::from_utf8_lossy(commit.message_bytes());
+    let trailers = git2::message_trailers_strs(&message)
+        .map(|trailers| {
+            trailers
+                .iter()
+                .map(|(key, value)| format!("{key}: {value}"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let message = message.trim_end();
+    // Trailers are always the last paragraph
+    let body = match message.rsplit_once("\n\n") {
+        Some((body, _)) if !trailers.is_empty() => body,
+        _ => message,
+    };
+    out.push('\n');
+    for line in body.lines() {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            let _ = writeln!(out, "    {line}");
+        }
+    }
+    if !trailers.is_empty() {
+        out.push('\n');
+        out.push_str("Trailers:\n");
+        for trailer in trailers {
+            let _ = writeln!(out, "    {trailer}");
+        }
+    }
+    Ok(())
+}
+
+/// Show the parts of the commit's diff that touch the lines of `hunk`
+fn write_diff(
+    out: &mut String,
+    blamer: &crate::blame::Blamer<'_>,
+    hunk: &crate::hunks::Hunk,
+) -> anyhow::Result<()> {
+    let repo = blamer.repo();
+    let commit = repo.find_commit(hunk.commit_id())?;
+    let path = hunk.orig_path();
+    let change = crate::diff::FileChange::new(repo, &commit, path)?
+        .with_context(|| format!("{} is not in {}", path.display(), commit.id()))?;
+    let start = hunk.orig_start_line();
+    let end = start + hunk.lines() - 1;
+    let selection = crate::diff::Selection::Hunks(vec![(start, end)]);
+    let lines = change.render(blamer, &selection)?;
+    for line in lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let location = "src/main.rs:42".parse::<Location>().unwrap();
+        assert_eq!(location.path, std::path::PathBuf::from("src/main.rs"));
+        assert_eq!(location.line, 42);
+        assert_eq!(
+            "a:b:3".parse::<Location>().unwrap().path.to_str(),
+            Some("a:b")
+        );
+        assert!("src/main.rs".parse::<Location>().is_err());
+        assert!("src/main.rs:0".parse::<Location>().is_err());
+        assert!(":3".parse::<Location>().is_err());
+    }
+}
@@ -0,0 +1,56 @@
+//! Track a file across renames
+
+/// The path `path`, as of `from`, had at `to`
+///
+/// Only renames along the first-parent history are followed.  If `to` isn't a first-parent
+/// ancestor of `from`, renames are followed all the way back to the root.
+pub(crate) fn path_at(
+    repo: &git2::Repository,
+    from: git2::Oid,
+    to: git2::Oid,
+    path: &std::path::Path,
+) -> anyhow::Result<std::path::PathBuf> {
+    let mut path = path.to_owned();
+    let mut revwalk = repo.revwalk()?;
+    revwalk.simplify_first_parent()?;
+    revwalk.set_sorting(git2::Sort::NONE)?;
+    revwalk.push(from)?;
+    for id in revwalk {
+        let id = id?;
+        if id == to {
+            break;
+        }
+        let commit = repo.find_commit(id)?;
+        if let Some(old) = renamed_from(repo, &commit, &path)? {
+            path = old;
+        }
+    }
+    Ok(path)
+}
+
+/// What `path` was called before `commit`, if `commit` renamed it
+pub(crate) fn renamed_from(
+    repo: &git2::Repository,
+    commit: &git2::Commit<'_>,
+    path: &std::path::Path,
+) -> anyhow::Result<Option<std::path::PathBuf>> {
+    let Ok(parent) = commit.parent(0) else {
+        return Ok(None);
+    };
+    let parent_tree = parent.tree()?;
+    let tree = commit.tree()?;
+    if parent_tree.get_path(path).is_ok() || tree.get_path(path).is_err() {
+        return Ok(None);
+    }
+
+    let mut diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)?;
+    let mut options = git2::DiffFindOptions::new();
+    options.renames(true);
+    diff.find_similar(Some(&mut options))?;
+    let old = diff
+        .deltas()
+        .filter(|d| d.status() == git2::Delta::Renamed)
+        .find(|d| d.new_file().path() == Some(path))
+        .and_then(|d| d.old_file().path().map(|p| p.to_owned()));
+    Ok(old)
+}
@@ -0,0 +1,344 @@
+//! Machine-readable output for blame results
+
+use crate::blame::Annotations;
+use crate::blame::Person;
+use crate::granularity::Tokens;
+use crate::hunks::Hunk;
+use crate::hunks::Hunks;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub(crate) enum Format {
+    /// Syntax-highlighted annotations for reading
+    #[default]
+    Text,
+    /// A JSON document with one record per hunk
+    Json,
+    /// `git blame --porcelain`, with a `dive-origin` header
+    Porcelain,
+    /// `git blame --line-porcelain`, with a `dive-origin` header
+    LinePorcelain,
+    /// `git blame --incremental`, with a `dive-origin` header
+    Incremental,
+}
+
+/// The selected lines of a hunk
+struct Segment<'h> {
+    hunk: &'h Hunk,
+    /// 1-based line in the final file
+    start: usize,
+    lines: usize,
+}
+
+impl Segment<'_> {
+    /// 1-based line in the original file
+    fn orig_start(&self) -> usize {
+        self.hunk.orig_start_line() + (self.start - self.hunk.final_start_line())
+    }
+}
+
+/// Split hunks so only lines for which `is_selected` returns `true` are included
+fn segments<'h>(
+    hunks: &'h Hunks,
+    line_count: usize,
+    is_selected: impl Fn(usize) -> bool,
+) -> Vec<Segment<'h>> {
+    let mut segments: Vec<Segment<'h>> = Vec::new();
+    for line_num in 1..=line_count {
+        if !is_selected(line_num) {
+            continue;
+        }
+        let hunk = hunks.get_line(line_num).unwrap_or_else(|| {
+            panic!("Mismatch in line numbers between dive ({line_num}) and git2")
+        });
+        if let Some(segment) = segments.last_mut() {
+            if std::ptr::eq(segment.hunk, hunk) && segment.start + segment.lines == line_num {
+                segment.lines += 1;
+                continue;
+            }
+        }
+        segments.push(Segment {
+            hunk,
+            start: line_num,
+            lines: 1,
+        });
+    }
+    segments
+}
+
+/// Write the blame as a JSON document
+///
+/// Only lines for which `is_selected` returns `true` are included, splitting hunks as needed.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_json(
+    output: &mut dyn std::io::Write,
+    path: &std::path::Path,
+    rev: &str,
+    origin_base: &str,
+    hunks: &Hunks,
+    annotations: &Annotations,
+    tokens: Option<&Tokens>,
+    lines: &[&str],
+    is_selected: impl Fn(usize) -> bool,
+) -> anyhow::Result<()> {
+    let records = segments(hunks, lines.len(), is_selected)
+        .into_iter()
+        .map(|segment| {
+            let hunk = segment.hunk;
+            let annotation = annotations
+                .get(hunk.commit_id())
+                .expect("all blame hunks are annotated");
+            HunkRecord {
+                commit: hunk.commit_id().to_string(),
+                short: annotation.short(),
+                origin: annotation.origin(),
+                author: PersonRecord::new(annotation.author()),
+                committer: PersonRecord::new(annotation.committer()),
+                summary: annotation.summary(),
+                pull_request: annotation.pull_request(),
+                orig_path: hunk.orig_path().to_string_lossy().into_owned(),
+                orig_start_line: segment.orig_start(),
+                final_start_line: segment.start,
+                ignored: hunk.ignored(),
+                unblamable: hunk.unblamable(),
+                lines: lines[segment.start - 1..][..segment.lines].to_vec(),
+                spans: tokens.map(|tokens| {
+                    (segment.start..segment.start + segment.lines)
+                        .map(|line_num| {
+                            tokens
+                                .line(line_num)
+                                .iter()
+                                .map(|span| SpanRecord {
+                                    start: span.range.start,
+                                    end: span.range.end,
+                                    commit: span.commit.to_string(),
+                                    origin: annotations
+                                        .get(span.commit)
+                                        .expect("all tokens are annotated")
+                                        .origin(),
+                                })
+                                .collect()
+                        })
+                        .collect()
+                }),
+            }
+        })
+        .collect();
+
+    let document = Document {
+        path: path.to_string_lossy().into_owned(),
+        rev,
+        origin_base,
+        hunks: records,
+    };
+    serde_json::to_writer_pretty(&mut *output, &document)?;
+    writeln!(output)?;
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct Document<'a> {
+    path: String,
+    /// The revision the lines were read from, like `WORKTREE` or `OLD` of `--reverse OLD..NEW`
+    rev: &'a str,
+    /// The revision each `origin` is relative to
+    origin_base: &'a str,
+    hunks: Vec<HunkRecord<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct HunkRecord<'a> {
+    commit: String,
+    short: &'a str,
+    origin: &'a str,
+    author: PersonRecord<'a>,
+    committer: PersonRecord<'a>,
+    summary: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pull_request: Option<&'a str>,
+    orig_path: String,
+    orig_start_line: usize,
+    final_start_line: usize,
+    ignored: bool,
+    unblamable: bool,
+    lines: Vec<&'a str>,
+    /// For each line, the commit behind each part of it, from `--granularity`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spans: Option<Vec<Vec<SpanRecord<'a>>>>,
+}
+
+#[derive(serde::Serialize)]
+struct SpanRecord<'a> {
+    /// Byte offset into the line
+    start: usize,
+    end: usize,
+    commit: String,
+    origin: &'a str,
+}
+
+#[derive(serde::Serialize)]
+struct PersonRecord<'a> {
+    name: &'a str,
+    email: &'a str,
+    /// Seconds since the Unix epoch
+    time: i64,
+    /// Timezone offset in minutes
+    offset: i32,
+}
+
+impl<'a> PersonRecord<'a> {
+    fn new(person: &'a Person) -> Self {
+        Self {
+            name: person.name(),
+            email: person.email(),
+            time: person.time().seconds(),
+            offset: person.time().offset_minutes(),
+        }
+    }
+}
+
+/// Write the blame in one of `git blame`'s porcelain formats
+///
+/// Commit details are followed by a `dive-origin` header with the relative origin.  As with all
+/// unknown headers, parsers for `git blame`'s output are expected to skip it.
+pub(crate) fn write_porcelain(
+    output: &mut dyn std::io::Write,
+    format: Format,
+    repo: &git2::Repository,
+    hunks: &Hunks,
+    annotations: &Annotations,
+    lines: &[&str],
+    is_selected: impl Fn(usize) -> bool,
+) -> anyhow::Result<()> {
+    let segments = segments(hunks, lines.len(), is_selected);
+
+    // Like git, always report the filename for commits that touched more than one path
+    let mut paths = std::collections::HashMap::<git2::Oid, &std::path::Path>::new();
+    let mut multiple_paths = std::collections::HashSet::new();
+    for segment in &segments {
+        let id = segment.hunk.commit_id();
+        let path = segment.hunk.orig_path();
+        if *paths.entry(id).or_insert(path) != path {
+            multiple_paths.insert(id);
+        }
+    }
+
+    let mut shown = std::collections::HashSet::new();
+    for segment in &segments {
+        let hunk = segment.hunk;
+        let id = hunk.commit_id();
+        let annotation = annotations.get(id).expect("all blame hunks are annotated");
+        let orig_start = segment.orig_start();
+        writeln!(
+            output,
+            "{id} {orig_start} {} {}",
+            segment.start, segment.lines
+        )?;
+
+        let repeat = format == Format::LinePorcelain;
+        let show_details = repeat || shown.insert(id);
+        if show_details {
+            write_details(output, annotation, hunk.boundary())?;
+        }
+        if show_details || multiple_paths.contains(&id) || format == Format::Incremental {
+            write_filename(output, repo, hunk)?;
+        }
+        if format == Format::Incremental {
+            continue;
+        }
+
+        for i in 0..segment.lines {
+            if i != 0 {
+                writeln!(output, "{id} {} {}", orig_start + i, segment.start + i)?;
+                if repeat {
+                    write_details(output, annotation, hunk.boundary())?;
+                    write_filename(output, repo, hunk)?;
+                }
+            }
+            writeln!(output, "\t{}", lines[segment.start - 1 + i])?;
+        }
+    }
+    Ok(())
+}
+
+fn write_details(
+    output: &mut dyn std::io::Write,
+    annotation: &crate::blame::Annotation,
+    boundary: bool,
+) -> std::io::Result<()> {
+    for (role, person) in [
+        ("author", annotation.author()),
+        ("committer", annotation.committer()),
+    ] {
+        writeln!(output, "{role} {}", person.name())?;
+        writeln!(output, "{role}-mail <{}>", person.email())?;
+        writeln!(output, "{role}-time {}", person.time().seconds())?;
+        writeln!(output, "{role}-tz {}", format_tz(person.time()))?;
+    }
+    writeln!(output, "summary {}", annotation.summary())?;
+    writeln!(output, "dive-origin {}", annotation.origin())?;
+    if boundary {
+        writeln!(output, "boundary")?;
+    }
+    Ok(())
+}
+
+fn write_filename(
+    output: &mut dyn std::io::Write,
+    repo: &git2::Repository,
+    hunk: &Hunk,
+) -> anyhow::Result<()> {
+    let path = hunk.orig_path();
+    // Uncommitted changes don't have a commit to look up
+    if !hunk.commit_id().is_zero() {
+        let commit = repo.find_commit(hunk.commit_id())?;
+        if let Ok(parent) = commit.parent(0) {
+            if parent.tree()?.get_path(path).is_ok() {
+                writeln!(output, "previous {} {}", parent.id(), quote_path(path))?;
+            }
+        }
+    }
+    writeln!(output, "filename {}", quote_path(path))?;
+    Ok(())
+}
+
+/// Format a timezone offset like git, e.g. `-0500`
+fn format_tz(time: git2::Time) -> String {
+    let offset = time.offset_minutes();
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.unsigned_abs();
+    format!("{sign}{:02}{:02}", offset / 60, offset % 60)
+}
+
+/// Quote a path like git does with `core.quotePath`
+fn quote_path(path: &std::path::Path) -> String {
+    let path = path.to_string_lossy();
+    let needs_quoting = path
+        .bytes()
+        .any(|b| b < 0x20 || b == b'"' || b == b'\\' || 0x7f <= b);
+    if !needs_quoting {
+        return path.into_owned();
+    }
+
+    let mut quoted = String::with_capacity(path.len() + 2);
+    quoted.push('"');
+    for b in path.bytes() {
+        match b {
+            b'\x07' => quoted.push_str("\\a"),
+            b'\x08' => quoted.push_str("\\b"),
+            b'\t' => quoted.push_str("\\t"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\x0b' => quoted.push_str("\\v"),
+            b'\x0c' => quoted.push_str("\\f"),
+            b'\r' => quoted.push_str("\\r"),
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b if !(0x20..0x7f).contains(&b) => {
+                use std::fmt::Write as _;
+                let _ = write!(quoted, "\\{b:03o}");
+            }
+            b => quoted.push(b as char),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -55,10 +55,14 @@ impl Config {
         for field in fields {
             let (section, name) = field
                 .name()
-                .split_once('.')
+                .rsplit_once('.')
                 .unwrap_or_else(|| panic!("field `{}` is missing a section", field.name()));
             if section != prior_section {
-                let _ = writeln!(&mut output, "[{section}]");
+                if let Some((section, subsection)) = section.split_once('.') {
+                    let _ = writeln!(&mut output, "[{section} \"{subsection}\"]");
+                } else {
+                    let _ = writeln!(&mut output, "[{section}]");
+                }
                 prior_section = section;
             }
             let value = field.dump(self);
@@ -94,6 +98,8 @@ pub(crate) trait ConfigSource {
     fn get_i64(&self, name: &str) -> anyhow::Result<i64>;
     fn get_string(&self, name: &str) -> anyhow::Result<String>;
     fn get_path(&self, name: &str) -> anyhow::Result<std::path::PathBuf>;
+    /// Every value of a multi-valued field, in the order they were set
+    fn get_multi_string(&self, name: &str) -> anyhow::Result<Vec<String>>;
 }
 
 impl ConfigSource for Config {
@@ -174,6 +180,27 @@ impl ConfigSource for Config {
             .expect("always a source")
             .get_path(name)
     }
+    fn get_multi_string(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        // Lowest priority first, so later values can override earlier ones
+        let sources = self.sources().collect::<Vec<_>>();
+        let mut values = Vec::new();
+        let mut found = false;
+        for config in sources.into_iter().rev() {
+            if let Ok(v) = config.get_multi_string(name) {
+                values.extend(v);
+                found = true;
+            }
+        }
+        if found {
+            Ok(values)
+        } else {
+            // Fallback to the first error
+            self.sources()
+                .next()
+                .expect("always a source")
+                .get_multi_string(name)
+        }
+    }
 }
 
 impl ConfigSource for git2::Config {
@@ -201,6 +228,18 @@ impl ConfigSource for git2::Config {
     fn get_path(&self, name: &str) -> anyhow::Result<std::path::PathBuf> {
         self.get_path(name).map_err(|e| e.into())
     }
+    fn get_multi_string(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        let mut values = Vec::new();
+        self.multivar(name, None)?.for_each(|entry| {
+            if let Some(value) = entry.value() {
+                values.push(value.to_owned());
+            }
+        })?;
+        if values.is_empty() {
+            anyhow::bail!("field is missing");
+        }
+        Ok(values)
+    }
 }
 
 pub(crate) struct GitConfig {
@@ -265,6 +304,9 @@ impl ConfigSource for GitConfig {
     fn get_path(&self, name: &str) -> anyhow::Result<std::path::PathBuf> {
         self.inner().get_path(name)
     }
+    fn get_multi_string(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        self.inner().get_multi_string(name)
+    }
 }
 
 impl std::fmt::Debug for GitConfig {
@@ -351,8 +393,8 @@ impl ConfigSource for InMemoryConfig {
         self.get_str(name).map(|_| self.name())
     }
     fn get_bool(&self, name: &str) -> anyhow::Result<bool> {
-        let v = self.get_str(name).unwrap_or("true");
-        v.parse::<bool>().map_err(|e| e.into())
+        self.get_str(name)
+            .and_then(|v| v.parse::<bool>().map_err(|e| e.into()))
     }
     fn get_i32(&self, name: &str) -> anyhow::Result<i32> {
         self.get_str(name)
@@ -368,6 +410,9 @@ impl ConfigSource for InMemoryConfig {
     fn get_path(&self, name: &str) -> anyhow::Result<std::path::PathBuf> {
         self.get_string(name).map(|v| v.into())
     }
+    fn get_multi_string(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        self.values.get(name).cloned().context("field is missing")
+    }
 }
 
 pub(crate) trait FieldReader<T> {
@@ -409,6 +454,13 @@ impl<C: ConfigSource> FieldReader<std::path::PathBuf> for C {
     }
 }
 
+impl<C: ConfigSource> FieldReader<Vec<String>> for C {
+    fn get_field(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        self.get_multi_string(name)
+            .with_context(|| anyhow::format_err!("failed to read `{name}`"))
+    }
+}
+
 impl<P: Parseable, C: ConfigSource> FieldReader<P> for C {
     fn get_field(&self, name: &str) -> anyhow::Result<P> {
         self.get_string(name)
@@ -533,6 +585,45 @@ where
     }
 }
 
+/// Reflect a field whose value isn't [`std::fmt::Display`], like an optional path or a list
+pub(crate) struct ReflectWith<F: Field> {
+    field: F,
+    display: fn(F::Output) -> String,
+}
+
+impl<F: Field> ReflectWith<F> {
+    pub(crate) const fn new(field: F, display: fn(F::Output) -> String) -> Self {
+        Self { field, display }
+    }
+}
+
+impl<F: Field> ReflectField for ReflectWith<F> {
+    fn name(&self) -> &'static str {
+        self.field.name()
+    }
+
+    fn dump(&self, config: &Config) -> String {
+        (self.display)(self.field.get_from(config))
+    }
+    fn get_source<'c>(&self, config: &'c Config) -> &'c str {
+        self.field.get_source(config).unwrap_or("unset")
+    }
+}
+
+pub(crate) fn display_optional(value: Option<String>) -> String {
+    value.unwrap_or_default()
+}
+
+pub(crate) fn display_path(value: Option<std::path::PathBuf>) -> String {
+    value
+        .map(|path| path.display().to_string())
+        .unwrap_or_default()
+}
+
+pub(crate) fn display_list(value: Vec<String>) -> String {
+    value.join(", ")
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 pub(crate) enum ColorWhen {
     Always,
@@ -0,0 +1,396 @@
+//! Attribute each word or token of a line to the commit that last changed it
+
+use crate::diff::FileChange;
+
+/// How finely to assign blame, from `--granularity`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub(crate) enum Granularity {
+    /// Whole lines, like `git blame`
+    #[default]
+    Line,
+    /// Runs of letters, digits, and underscores, with other characters on their own
+    Word,
+    /// Tokens from the syntax highlighter
+    Token,
+}
+
+/// Part of a line, along with the commit that last changed it
+#[derive(Clone, Debug)]
+pub(crate) struct Span {
+    /// Byte offsets into the line
+    pub(crate) range: std::ops::Range<usize>,
+    pub(crate) commit: git2::Oid,
+}
+
+/// The commit behind each piece of each line of a file
+pub(crate) struct Tokens {
+    /// Spans for each line, in order, with lines that weren't looked at left empty
+    lines: Vec<Vec<Span>>,
+}
+
+impl Tokens {
+    /// Spans for the 1-based `line_num`, empty if it wasn't looked at
+    pub(crate) fn line(&self, line_num: usize) -> &[Span] {
+        self.lines
+            .get(line_num - 1)
+            .map(|spans| spans.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Every commit spans are attributed to
+    pub(crate) fn commits(&self) -> std::collections::HashSet<git2::Oid> {
+        self.lines
+            .iter()
+            .flatten()
+            .map(|span| span.commit)
+            .collect()
+    }
+}
+
+/// Splits lines up for [`Granularity`]
+pub(crate) struct Tokenizer<'s> {
+    granularity: Granularity,
+    syntax: &'s syntect::parsing::SyntaxReference,
+    syntax_set: &'s syntect::parsing::SyntaxSet,
+}
+
+impl<'s> Tokenizer<'s> {
+    pub(crate) fn new(
+        granularity: Granularity,
+        syntax: &'s syntect::parsing::SyntaxReference,
+        syntax_set: &'s syntect::parsing::SyntaxSet,
+    ) -> Self {
+        Self {
+            granularity,
+            syntax,
+            syntax_set,
+        }
+    }
+
+    /// Byte ranges of each token in `line`, covering all of it
+    ///
+    /// Each line is parsed on its own, so tokens inside of multi-line constructs, like block
+    /// comments, may be split differently than when highlighting.
+    fn tokenize(&self, line: &str) -> Vec<std::ops::Range<usize>> {
+        let mut boundaries = vec![0];
+        match self.granularity {
+            Granularity::Line => {}
+            Granularity::Word => {
+                let is_word = |c: char| c.is_alphanumeric() || c == '_';
+                let mut prev = None;
+                for (i, c) in line.char_indices() {
+                    let class = if is_word(c) {
+                        Some(0)
+                    } else if c.is_whitespace() {
+                        Some(1)
+                    } else {
+                        // Punctuation stands on its own
+                        None
+                    };
+                    if class.is_none() || class != prev {
+                        boundaries.push(i);
+                    }
+                    prev = class;
+                }
+            }
+            Granularity::Token => {
+                // Too long to be worth parsing, like in `Highlighter`
+                if line.len() <= 1024 * 16 {
+                    let mut state = syntect::parsing::ParseState::new(self.syntax);
+                    let line = format!("{line}\n");
+                    if let Ok(ops) = state.parse_line(&line, self.syntax_set) {
+                        boundaries.extend(ops.into_iter().map(|(i, _)| i));
+                    }
+                }
+                // Keep whitespace apart from what it separates
+                let mut prev = None;
+                for (i, c) in line.char_indices() {
+                    let class = Some(c.is_whitespace());
+                    if class != prev {
+                        boundaries.push(i);
+                    }
+                    prev = class;
+                }
+            }
+        }
+        boundaries.push(line.len());
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        boundaries
+            .windows(2)
+            .filter(|w| w[1] <= line.len())
+            .map(|w| w[0]..w[1])
+            .collect()
+    }
+}
+
+/// A line of the blamed file, while its tokens are being followed back through history
+struct Track {
+    /// 0-based line in the blamed file
+    line: usize,
+    /// 1-based line in the version being looked at
+    current: usize,
+    /// Tokens still unattributed, as their index in the blamed line and in the current line
+    tokens: Vec<(usize, usize)>,
+}
+
+/// Follows tokens of a file back until each one is attributed
+pub(crate) struct Attribution<'t> {
+    tokenizer: &'t Tokenizer<'t>,
+    /// Tokens of the blamed file
+    tokens: Vec<Vec<std::ops::Range<usize>>>,
+    owners: Vec<Vec<Option<git2::Oid>>>,
+    tracks: Vec<Track>,
+    /// The version of the file being looked at
+    current: String,
+}
+
+impl<'t> Attribution<'t> {
+    /// Prepare to attribute `is_selected` lines of `file`
+    pub(crate) fn new(
+        tokenizer: &'t Tokenizer<'t>,
+        file: &str,
+        is_selected: impl Fn(usize) -> bool,
+    ) -> Self {
+        let file = file.strip_prefix('\u{feff}').unwrap_or(file);
+        let mut tokens = Vec::new();
+        let mut owners = Vec::new();
+        let mut tracks = Vec::new();
+        for (i, line) in file.lines().enumerate() {
+            let line_tokens = if is_selected(i + 1) {
+                tokenizer.tokenize(line)
+            } else {
+                Vec::new()
+            };
+            if !line_tokens.is_empty() {
+                tracks.push(Track {
+                    line: i,
+                    current: i + 1,
+                    tokens: (0..line_tokens.len()).map(|t| (t, t)).collect(),
+                });
+            }
+            owners.push(vec![None; line_tokens.len()]);
+            tokens.push(line_tokens);
+        }
+        Self {
+            tokenizer,
+            tokens,
+            owners,
+            tracks,
+            current: file.to_owned(),
+        }
+    }
+
+    /// Whether every token has been attributed
+    pub(crate) fn is_done(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Attribute what changed between `old` and the current version to `commit`
+    ///
+    /// `old` is `None` when `commit` added the file.
+    pub(crate) fn step(&mut self, commit: git2::Oid, old: Option<String>) -> anyhow::Result<()> {
+        let Some(old) = old else {
+            for track in std::mem::take(&mut self.tracks) {
+                for (token, _) in track.tokens {
+                    self.owners[track.line][token] = Some(commit);
+                }
+            }
+            return Ok(());
+        };
+        let map = crate::diff::LineMap::from_buffers(old.as_bytes(), self.current.as_bytes())?;
+        let old_lines = old.lines().collect::<Vec<_>>();
+        let current_lines = self.current.lines().collect::<Vec<_>>();
+        for track in &mut self.tracks {
+            if let Some(line) = map.new_to_old(track.current) {
+                track.current = line;
+                continue;
+            }
+            let old_line = map
+                .new_to_old_fuzzy(track.current)
+                .and_then(|line| Some((line, *old_lines.get(line - 1)?)));
+            let Some((old_num, old_line)) = old_line else {
+                for (token, _) in track.tokens.drain(..) {
+                    self.owners[track.line][token] = Some(commit);
+                }
+                continue;
+            };
+            let current_line = current_lines.get(track.current - 1).copied().unwrap_or("");
+            let current_tokens = self.tokenizer.tokenize(current_line);
+            let old_tokens = self.tokenizer.tokenize(old_line);
+            let matches = match_tokens(
+                &current_tokens
+                    .iter()
+                    .map(|r| &current_line[r.clone()])
+                    .collect::<Vec<_>>(),
+                &old_tokens
+                    .iter()
+                    .map(|r| &old_line[r.clone()])
+                    .collect::<Vec<_>>(),
+            );
+            let owners = &mut self.owners[track.line];
+            track.tokens.retain_mut(|(token, current)| {
+                match matches.get(*current).copied().flatten() {
+                    Some(old) => {
+                        *current = old;
+                        true
+                    }
+                    None => {
+                        owners[*token] = Some(commit);
+                        false
+                    }
+                }
+            });
+            track.current = old_num;
+        }
+        self.tracks.retain(|track| !track.tokens.is_empty());
+        self.current = old;
+        Ok(())
+    }
+
+    /// Follow the rest of the tokens through the first-parent history of `head`
+    pub(crate) fn walk(
+        &mut self,
+        repo: &git2::Repository,
+        head: &str,
+        path: &std::path::Path,
+    ) -> anyhow::Result<()> {
+        let mut path = path.to_owned();
+        for entry in crate::blame::first_parent_history(repo, head)? {
+            if self.is_done() {
+                break;
+            }
+            let (_, id) = entry?;
+            let commit = repo.find_commit(id)?;
+            if crate::diff::is_unchanged(&commit, &path)? {
+                continue;
+            }
+            let Some(change) = FileChange::new(repo, &commit, &path)? else {
+                break;
+            };
+            let old = if change.is_added() {
+                None
+            } else {
+                Some(crate::blame::convert_file(
+                    change.old_content(),
+                    change.old_path(),
+                )?)
+            };
+            self.step(id, old)?;
+            path = change.old_path().to_owned();
+        }
+        Ok(())
+    }
+
+    /// Merge neighboring tokens from the same commit, falling back to `line_commit` for any left
+    /// unattributed
+    pub(crate) fn finish(self, line_commit: impl Fn(usize) -> Option<git2::Oid>) -> Tokens {
+        let lines = self
+            .tokens
+            .into_iter()
+            .zip(self.owners)
+            .enumerate()
+            .map(|(i, (tokens, owners))| {
+                let mut spans: Vec<Span> = Vec::new();
+                for (range, owner) in tokens.into_iter().zip(owners) {
+                    let Some(commit) = owner.or_else(|| line_commit(i + 1)) else {
+                        continue;
+                    };
+                    match spans.last_mut() {
+                        Some(last) if last.commit == commit && last.range.end == range.start => {
+                            last.range.end = range.end;
+                        }
+                        _ => spans.push(Span { range, commit }),
+                    }
+                }
+                spans
+            })
+            .collect();
+        Tokens { lines }
+    }
+}
+
+/// Pair up tokens that are the same in both versions, by longest common subsequence
+///
+/// Returns the index in `old` for each token in `new` that was kept.
+fn match_tokens(new: &[&str], old: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; new.len()];
+    // Give up on pathologically long lines rather than taking forever
+    if 1_000_000 < new.len() * old.len() {
+        return matches;
+    }
+    let width = old.len() + 1;
+    let mut lengths = vec![0_u32; (new.len() + 1) * width];
+    for i in (0..new.len()).rev() {
+        for j in (0..old.len()).rev() {
+            lengths[i * width + j] = if new[i] == old[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < new.len() && j < old.len() {
+        if new[i] == old[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn words() {
+        let syntax_set = crate::assets::integrated_syntaxes();
+        let syntax = syntax_set.find_syntax_plain_text();
+        let tokenizer = Tokenizer::new(Granularity::Word, syntax, &syntax_set);
+        let line = "let foo_bar = baz(1);";
+        let words = tokenizer
+            .tokenize(line)
+            .into_iter()
+            .map(|r| &line[r])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            [
+                "let", " ", "foo_bar", " ", "=", " ", "baz", "(", "1", ")", ";"
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens() {
+        let syntax_set = crate::assets::integrated_syntaxes();
+        let syntax = syntax_set.find_syntax_by_extension("rs").unwrap();
+        let tokenizer = Tokenizer::new(Granularity::Token, syntax, &syntax_set);
+        let line = r#"let x = "a b";"#;
+        let tokens = tokenizer
+            .tokenize(line)
+            .into_iter()
+            .map(|r| &line[r])
+            .collect::<Vec<_>>();
+        assert!(tokens.contains(&"let"), "{tokens:?}");
+        assert!(tokens.contains(&"x"), "{tokens:?}");
+        assert_eq!(tokens.concat(), line);
+    }
+
+    #[test]
+    fn lcs() {
+        assert_eq!(
+            match_tokens(&["a", "b", "c"], &["a", "x", "c"]),
+            [Some(0), None, Some(2)]
+        );
+        assert_eq!(match_tokens(&["a", "b"], &[]), [None, None]);
+        assert_eq!(match_tokens(&["b", "a"], &["a", "b"]), [None, Some(0)]);
+    }
+}
@@ -0,0 +1,342 @@
+//! Columns describing the commit a line came from
+
+use crate::blame::Annotation;
+
+/// The columns to show, from `dive.gutter`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Gutter {
+    columns: Vec<Column>,
+}
+
+impl Gutter {
+    pub(crate) fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+}
+
+impl Default for Gutter {
+    fn default() -> Self {
+        Self {
+            columns: vec![Column::Origin],
+        }
+    }
+}
+
+impl std::fmt::Display for Gutter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, column) in self.columns.iter().enumerate() {
+            if i != 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(column.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Gutter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let columns = s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|c| !c.is_empty())
+            .map(|c| c.parse::<Column>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { columns })
+    }
+}
+
+impl crate::git2_config::Parseable for Gutter {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        <Self as std::str::FromStr>::from_str(s)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Column {
+    /// Relative reference, like `HEAD~10`, falling back to the short id
+    Origin,
+    /// Abbreviated commit id
+    Id,
+    AuthorName,
+    AuthorEmail,
+    /// Author date, like `2023-04-13`
+    AuthorDate,
+    /// Author date relative to now, like `3 weeks ago`
+    AuthorAge,
+    /// First line of the commit message
+    Summary,
+    /// Number of the pull request the commit was merged in, like `#123`
+    PullRequest,
+}
+
+impl Column {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Origin => "origin",
+            Self::Id => "id",
+            Self::AuthorName => "author-name",
+            Self::AuthorEmail => "author-email",
+            Self::AuthorDate => "author-date",
+            Self::AuthorAge => "author-age",
+            Self::Summary => "summary",
+            Self::PullRequest => "pr",
+        }
+    }
+
+    /// Longest the column may be, in terminal columns
+    fn max_width(&self) -> Option<usize> {
+        match self {
+            Self::Origin | Self::Id | Self::AuthorDate | Self::AuthorAge | Self::PullRequest => {
+                None
+            }
+            Self::AuthorName => Some(20),
+            Self::AuthorEmail => Some(30),
+            Self::Summary => Some(40),
+        }
+    }
+
+    fn value(&self, annotation: &Annotation, now: i64) -> String {
+        match self {
+            Self::Origin => annotation.origin().to_owned(),
+            Self::Id => annotation.short().to_owned(),
+            Self::AuthorName => annotation.author().name().to_owned(),
+            Self::AuthorEmail => annotation.author().email().to_owned(),
+            Self::AuthorDate => format_date(annotation.author().time()),
+            Self::AuthorAge => format_age(annotation.author().time(), now),
+            Self::Summary => annotation.summary().to_owned(),
+            Self::PullRequest => annotation.pull_request().unwrap_or_default().to_owned(),
+        }
+    }
+}
+
+impl std::str::FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let column = match s {
+            "origin" => Self::Origin,
+            "id" => Self::Id,
+            "author-name" | "author" => Self::AuthorName,
+            "author-email" => Self::AuthorEmail,
+            "author-date" | "date" => Self::AuthorDate,
+            "author-age" | "age" => Self::AuthorAge,
+            "summary" => Self::Summary,
+            "pr" | "pull-request" => Self::PullRequest,
+            _ => anyhow::bail!(
+                "unsupported gutter column `{s}`, expected one of `origin`, `id`, `author-name`, `author-email`, `author-date`, `author-age`, `summary`, `pr`"
+            ),
+        };
+        Ok(column)
+    }
+}
+
+/// Gutter text for each commit, padded to line up
+pub(crate) struct Layout {
+    columns: Vec<(Column, usize)>,
+    now: i64,
+}
+
+impl Layout {
+    /// Size each column to fit its values for `annotations`
+    pub(crate) fn new<'a>(
+        gutter: &Gutter,
+        annotations: impl Iterator<Item = &'a Annotation> + Clone,
+    ) -> Self {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let columns = gutter
+            .columns()
+            .iter()
+            .map(|column| {
+                let width = annotations
+                    .clone()
+                    .map(|a| display_width(&column.value(a, now)))
+                    .max()
+                    .unwrap_or(0);
+                let width = column.max_width().map(|m| width.min(m)).unwrap_or(width);
+                (*column, width)
+            })
+            .collect();
+        Self { columns, now }
+    }
+
+    /// Terminal columns needed, including separating spaces
+    pub(crate) fn width(&self) -> usize {
+        let widths = self.columns.iter().map(|(_, w)| *w).sum::<usize>();
+        widths + self.columns.len().saturating_sub(1)
+    }
+
+    /// Describe `annotation`
+    pub(crate) fn render(&self, annotation: &Annotation) -> String {
+        let mut rendered = String::new();
+        for (i, (column, width)) in self.columns.iter().enumerate() {
+            if i != 0 {
+                rendered.push(' ');
+            }
+            let value = column.value(annotation, self.now);
+            rendered.push_str(&pad(&truncate(&value, *width), *width));
+        }
+        rendered
+    }
+}
+
+/// Terminal columns `s` takes up
+pub(crate) fn display_width(s: &str) -> usize {
+    unicode_width::UnicodeWidthStr::width(s)
+}
+
+/// Pad `s` with spaces to `width` terminal columns
+pub(crate) fn pad(s: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(s));
+    format!("{s}{:padding$}", "")
+}
+
+/// Truncate `s` to `width` terminal columns, marking it with `…` when truncated
+pub(crate) fn truncate(s: &str, width: usize) -> std::borrow::Cow<'_, str> {
+    if display_width(s) <= width {
+        return s.into();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        used += unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+        // Leave room for the `…`
+        if width <= used {
+            break;
+        }
+        truncated.push(c);
+    }
+    if width != 0 {
+        truncated.push('…');
+    }
+    truncated.into()
+}
+
+/// Truncate the start of `s` to `width` terminal columns, for keeping the end of paths
+pub(crate) fn truncate_start(s: &str, width: usize) -> std::borrow::Cow<'_, str> {
+    if display_width(s) <= width {
+        return s.into();
+    }
+    let mut start = s.len();
+    let mut used = 0;
+    for (i, c) in s.char_indices().rev() {
+        used += unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+        // Leave room for the `…`
+        if width <= used {
+            break;
+        }
+        start = i;
+    }
+    if width == 0 {
+        return "".into();
+    }
+    format!("…{}", &s[start..]).into()
+}
+
+/// Format like `git log --date=short`, in the author's timezone
+pub(crate) fn format_date(time: git2::Time) -> String {
+    let local = time.seconds() + i64::from(time.offset_minutes()) * 60;
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Format like `git log --date=relative`
+pub(crate) fn format_age(time: git2::Time, now: i64) -> String {
+    let seconds = now - time.seconds();
+    if seconds < 0 {
+        return "in the future".to_owned();
+    }
+    let plural = |n: i64, unit: &str| {
+        if n == 1 {
+            format!("{n} {unit} ago")
+        } else {
+            format!("{n} {unit}s ago")
+        }
+    };
+    let minutes = (seconds + 30) / 60;
+    let hours = (minutes + 30) / 60;
+    let days = (hours + 12) / 24;
+    if seconds < 90 {
+        plural(seconds, "second")
+    } else if minutes < 90 {
+        plural(minutes, "minute")
+    } else if hours < 36 {
+        plural(hours, "hour")
+    } else if days < 14 {
+        plural(days, "day")
+    } else if days < 70 {
+        plural((days + 3) / 7, "week")
+    } else if days < 365 {
+        plural((days + 15) / 30, "month")
+    } else {
+        plural((days + 183) / 365, "year")
+    }
+}
+
+/// Convert days since the Unix epoch to `(year, month, day)`
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let gutter = "origin, author-name,age".parse::<Gutter>().unwrap();
+        assert_eq!(gutter.to_string(), "origin,author-name,author-age");
+        assert!("origin,bogus".parse::<Gutter>().is_err());
+    }
+
+    #[test]
+    fn date() {
+        assert_eq!(format_date(git2::Time::new(0, 0)), "1970-01-01");
+        assert_eq!(format_date(git2::Time::new(1681344000, 0)), "2023-04-13");
+        assert_eq!(format_date(git2::Time::new(1681344000, -60)), "2023-04-12");
+        assert_eq!(format_date(git2::Time::new(951782400, 0)), "2000-02-29");
+    }
+
+    #[test]
+    fn age() {
+        let now = 1_000_000_000;
+        assert_eq!(format_age(git2::Time::new(now - 1, 0), now), "1 second ago");
+        assert_eq!(
+            format_age(git2::Time::new(now - 3 * 3600, 0), now),
+            "3 hours ago"
+        );
+        assert_eq!(
+            format_age(git2::Time::new(now - 21 * 86_400, 0), now),
+            "3 weeks ago"
+        );
+        assert_eq!(
+            format_age(git2::Time::new(now - 2 * 365 * 86_400, 0), now),
+            "2 years ago"
+        );
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(pad("日本", 5), "日本 ");
+        assert_eq!(truncate("日本語", 5), "日本…");
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("abc", 5), "abc");
+        assert_eq!(truncate_start("src/lib.rs", 8), "…/lib.rs");
+        assert_eq!(truncate_start("src/lib.rs", 10), "src/lib.rs");
+    }
+}
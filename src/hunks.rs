@@ -0,0 +1,406 @@
+use anyhow::Context as _;
+
+/// Line attribution for a file, independent of how it was computed
+pub(crate) struct Hunks {
+    hunks: Vec<Hunk>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Hunk {
+    commit_id: git2::Oid,
+    orig_path: std::path::PathBuf,
+    orig_start_line: usize,
+    final_start_line: usize,
+    lines: usize,
+    /// Tracked back to the root commit
+    boundary: bool,
+    /// Re-attributed past a commit from `--ignore-rev`
+    ignored: bool,
+    /// Could not be re-attributed past a commit from `--ignore-rev`
+    unblamable: bool,
+}
+
+/// Attribution for a single line, for when hunks need to be split up
+#[derive(Clone, Debug)]
+struct Line {
+    commit_id: git2::Oid,
+    orig_path: std::path::PathBuf,
+    orig_line: usize,
+    boundary: bool,
+    ignored: bool,
+    unblamable: bool,
+}
+
+impl Hunks {
+    pub(crate) fn blame(
+        repo: &git2::Repository,
+        path: &std::path::Path,
+        newest_commit: git2::Oid,
+        options: &mut git2::BlameOptions,
+    ) -> anyhow::Result<Self> {
+        options.newest_commit(newest_commit);
+        let blame = repo.blame_file(path, Some(options))?;
+        Ok(Self::from_blame(&blame, path))
+    }
+
+    /// Blame `buffer`, a modified version of `path` at `newest_commit`
+    ///
+    /// Lines not in `newest_commit` are attributed to the zero id, like libgit2 does.
+    pub(crate) fn blame_buffer(
+        repo: &git2::Repository,
+        path: &std::path::Path,
+        newest_commit: git2::Oid,
+        buffer: &[u8],
+        options: &mut git2::BlameOptions,
+    ) -> anyhow::Result<Self> {
+        let commit = repo.find_commit(newest_commit)?;
+        if read_blob(repo, &commit, path)?.is_none() {
+            // Added since `newest_commit`, so nothing to blame against
+            let lines = (1..=count_lines(buffer)).map(|i| {
+                let line = Line {
+                    commit_id: git2::Oid::zero(),
+                    orig_path: path.to_owned(),
+                    orig_line: i,
+                    boundary: false,
+                    ignored: false,
+                    unblamable: false,
+                };
+                (i, line)
+            });
+            return Ok(Self::from_lines(lines));
+        }
+
+        options.newest_commit(newest_commit);
+        let blame = repo.blame_file(path, Some(options))?;
+        let blame = blame.blame_buffer(buffer)?;
+        Ok(Self::from_blame(&blame, path))
+    }
+
+    pub(crate) fn from_blame(blame: &git2::Blame<'_>, path: &std::path::Path) -> Self {
+        let hunks = blame
+            .iter()
+            .map(|hunk| Hunk {
+                commit_id: hunk.orig_commit_id(),
+                orig_path: hunk.path().unwrap_or(path).to_owned(),
+                orig_start_line: hunk.orig_start_line(),
+                final_start_line: hunk.final_start_line(),
+                lines: hunk.lines_in_hunk(),
+                boundary: hunk.is_boundary(),
+                ignored: false,
+                unblamable: false,
+            })
+            .collect();
+        Self { hunks }
+    }
+
+    /// Annotate each line of `path` at `old` with the last commit it was present in on the way
+    /// to `new`
+    ///
+    /// This only follows the first-parent history, like the forward blame.
+    pub(crate) fn reverse(
+        repo: &git2::Repository,
+        old: &git2::Commit<'_>,
+        new: &git2::Commit<'_>,
+        path: &std::path::Path,
+    ) -> anyhow::Result<Self> {
+        let lines = forward(repo, old, new, path)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, (id, _))| {
+                let line = Line {
+                    commit_id: id,
+                    orig_path: path.to_owned(),
+                    orig_line: i + 1,
+                    boundary: false,
+                    ignored: false,
+                    unblamable: false,
+                };
+                (i + 1, line)
+            });
+        Ok(Self::from_lines(lines))
+    }
+
+    /// Re-attribute lines from `ignored` commits to what those commits replaced
+    ///
+    /// Like `git blame --ignore-rev`, a changed line is paired with the line it most likely
+    /// replaced in the parent commit.  Lines without a counterpart are left attributed to the
+    /// ignored commit and marked as unblamable.
+    pub(crate) fn ignore_revs(
+        self,
+        repo: &git2::Repository,
+        ignored: &std::collections::HashSet<git2::Oid>,
+        options: &mut git2::BlameOptions,
+    ) -> anyhow::Result<Self> {
+        if !self.hunks.iter().any(|h| ignored.contains(&h.commit_id)) {
+            return Ok(self);
+        }
+
+        let mut parents = ParentCache::default();
+        let mut lines = Vec::new();
+        for hunk in self.hunks {
+            for i in 0..hunk.lines {
+                let mut line = Line {
+                    commit_id: hunk.commit_id,
+                    orig_path: hunk.orig_path.clone(),
+                    orig_line: hunk.orig_start_line + i,
+                    boundary: hunk.boundary,
+                    ignored: hunk.ignored,
+                    unblamable: hunk.unblamable,
+                };
+                // Each step moves to a parent, so this always terminates
+                while ignored.contains(&line.commit_id) && !line.unblamable {
+                    if let Some(parent_line) = parents.blame_parent(repo, &line, options)? {
+                        line = Line {
+                            ignored: true,
+                            ..parent_line
+                        };
+                    } else {
+                        line.unblamable = true;
+                    }
+                }
+                lines.push((hunk.final_start_line + i, line));
+            }
+        }
+        Ok(Self::from_lines(lines))
+    }
+
+    /// Merge lines, keyed by their final line number, back into hunks
+    fn from_lines(lines: impl IntoIterator<Item = (usize, Line)>) -> Self {
+        let mut hunks: Vec<Hunk> = Vec::new();
+        for (final_line, line) in lines {
+            if let Some(hunk) = hunks.last_mut() {
+                if hunk.final_start_line + hunk.lines == final_line
+                    && hunk.commit_id == line.commit_id
+                    && hunk.orig_path == line.orig_path
+                    && hunk.orig_start_line + hunk.lines == line.orig_line
+                    && hunk.boundary == line.boundary
+                    && hunk.ignored == line.ignored
+                    && hunk.unblamable == line.unblamable
+                {
+                    hunk.lines += 1;
+                    continue;
+                }
+            }
+            hunks.push(Hunk {
+                commit_id: line.commit_id,
+                orig_path: line.orig_path,
+                orig_start_line: line.orig_line,
+                final_start_line: final_line,
+                lines: 1,
+                boundary: line.boundary,
+                ignored: line.ignored,
+                unblamable: line.unblamable,
+            });
+        }
+        Self { hunks }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Hunk> {
+        self.hunks.iter()
+    }
+
+    pub(crate) fn get_line(&self, line: usize) -> Option<&Hunk> {
+        let index = self
+            .hunks
+            .partition_point(|h| h.final_start_line + h.lines <= line);
+        self.hunks.get(index).filter(|h| h.final_start_line <= line)
+    }
+}
+
+impl Hunk {
+    pub(crate) fn commit_id(&self) -> git2::Oid {
+        self.commit_id
+    }
+
+    pub(crate) fn orig_path(&self) -> &std::path::Path {
+        &self.orig_path
+    }
+
+    pub(crate) fn orig_start_line(&self) -> usize {
+        self.orig_start_line
+    }
+
+    pub(crate) fn final_start_line(&self) -> usize {
+        self.final_start_line
+    }
+
+    pub(crate) fn lines(&self) -> usize {
+        self.lines
+    }
+
+    pub(crate) fn boundary(&self) -> bool {
+        self.boundary
+    }
+
+    pub(crate) fn ignored(&self) -> bool {
+        self.ignored
+    }
+
+    pub(crate) fn unblamable(&self) -> bool {
+        self.unblamable
+    }
+}
+
+/// Blames of parent commits, reused across the lines of a hunk
+#[derive(Default)]
+struct ParentCache {
+    line_maps:
+        std::collections::HashMap<(git2::Oid, std::path::PathBuf), Option<crate::diff::LineMap>>,
+    blames: std::collections::HashMap<(git2::Oid, std::path::PathBuf), Hunks>,
+}
+
+impl ParentCache {
+    /// Find what `line` replaced in the first parent of its commit
+    fn blame_parent(
+        &mut self,
+        repo: &git2::Repository,
+        line: &Line,
+        options: &mut git2::BlameOptions,
+    ) -> anyhow::Result<Option<Line>> {
+        let commit = repo.find_commit(line.commit_id)?;
+        let Ok(parent) = commit.parent(0) else {
+            return Ok(None);
+        };
+
+        let key = (commit.id(), line.orig_path.clone());
+        if !self.line_maps.contains_key(&key) {
+            let map = match (
+                read_blob(repo, &parent, &line.orig_path)?,
+                read_blob(repo, &commit, &line.orig_path)?,
+            ) {
+                (Some(old), Some(new)) => Some(crate::diff::LineMap::from_buffers(
+                    old.content(),
+                    new.content(),
+                )?),
+                _ => None,
+            };
+            self.line_maps.insert(key.clone(), map);
+        }
+        let Some(parent_line) = self.line_maps[&key]
+            .as_ref()
+            .and_then(|map| map.new_to_old_fuzzy(line.orig_line))
+        else {
+            return Ok(None);
+        };
+
+        let key = (parent.id(), line.orig_path.clone());
+        if !self.blames.contains_key(&key) {
+            let blame = Hunks::blame(repo, &line.orig_path, parent.id(), options)?;
+            self.blames.insert(key.clone(), blame);
+        }
+        let Some(hunk) = self.blames[&key].get_line(parent_line) else {
+            return Ok(None);
+        };
+        Ok(Some(Line {
+            commit_id: hunk.commit_id,
+            orig_path: hunk.orig_path.clone(),
+            orig_line: hunk.orig_start_line + (parent_line - hunk.final_start_line),
+            boundary: hunk.boundary,
+            ignored: hunk.ignored,
+            unblamable: hunk.unblamable,
+        }))
+    }
+}
+
+/// Follow each line of `path` at `old` through the first-parent history up to `new`
+///
+/// For each line, returns the last commit it was present in and the commit that changed or
+/// removed it, if any did.
+pub(crate) fn forward(
+    repo: &git2::Repository,
+    old: &git2::Commit<'_>,
+    new: &git2::Commit<'_>,
+    path: &std::path::Path,
+) -> anyhow::Result<Vec<(git2::Oid, Option<git2::Oid>)>> {
+    let mut history = Vec::new();
+    let mut revwalk = repo.revwalk()?;
+    revwalk.simplify_first_parent()?;
+    revwalk.set_sorting(git2::Sort::NONE)?;
+    revwalk.push(new.id())?;
+    let mut found = false;
+    for id in revwalk {
+        let id = id?;
+        if id == old.id() {
+            found = true;
+            break;
+        }
+        history.push(repo.find_commit(id)?);
+    }
+    if !found {
+        anyhow::bail!(
+            "{} is not a first-parent ancestor of {}",
+            old.id(),
+            new.id()
+        );
+    }
+    history.reverse();
+
+    let old_blob = read_blob(repo, old, path)?
+        .with_context(|| format!("Could not read {} at {}", path.display(), old.id()))?;
+    let line_count = count_lines(old_blob.content());
+
+    // For each line in `old`: where it is in the current commit, the last commit it was seen in,
+    // and the commit that took it away
+    let mut positions = (1..=line_count).map(Some).collect::<Vec<_>>();
+    let mut last_seen = vec![old.id(); line_count];
+    let mut next = vec![None; line_count];
+    let mut prev_blob = old_blob;
+    for commit in history {
+        let Some(blob) = read_blob(repo, &commit, path)? else {
+            // Removed, so nothing can be seen past here
+            for (position, next) in positions.iter().zip(next.iter_mut()) {
+                if position.is_some() {
+                    *next = Some(commit.id());
+                }
+            }
+            break;
+        };
+        if blob.id() != prev_blob.id() {
+            let map = crate::diff::LineMap::from_buffers(prev_blob.content(), blob.content())?;
+            for (position, next) in positions.iter_mut().zip(next.iter_mut()) {
+                if let Some(p) = *position {
+                    *position = map.old_to_new(p);
+                    if position.is_none() {
+                        *next = Some(commit.id());
+                    }
+                }
+            }
+        }
+        for (position, last) in positions.iter().zip(last_seen.iter_mut()) {
+            if position.is_some() {
+                *last = commit.id();
+            }
+        }
+        if positions.iter().all(|p| p.is_none()) {
+            break;
+        }
+        prev_blob = blob;
+    }
+
+    Ok(last_seen.into_iter().zip(next).collect())
+}
+
+/// The contents of `path` in `commit`, or `None` if it isn't there
+pub(crate) fn read_blob<'r>(
+    repo: &'r git2::Repository,
+    commit: &git2::Commit<'_>,
+    path: &std::path::Path,
+) -> anyhow::Result<Option<git2::Blob<'r>>> {
+    let tree = commit.tree()?;
+    let entry = match tree.get_path(path) {
+        Ok(entry) => entry,
+        Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    Ok(Some(blob))
+}
+
+fn count_lines(content: &[u8]) -> usize {
+    let newlines = content.iter().filter(|b| **b == b'\n').count();
+    if content.last().is_some_and(|b| *b != b'\n') {
+        newlines + 1
+    } else {
+        newlines
+    }
+}
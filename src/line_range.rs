@@ -0,0 +1,262 @@
+/// A `-L` argument, like `git blame`
+#[derive(Clone, Debug)]
+pub(crate) enum LineRange {
+    /// `<start>,<end>`
+    Lines { start: Bound, end: Bound },
+    /// `:<funcname>`
+    Function(Pattern),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Bound {
+    /// Not specified, defaulting to the start or end of the file
+    Unset,
+    Line(usize),
+    /// `+<offset>` or `-<offset>`, only valid for `<end>`
+    Offset(isize),
+    /// `/<regex>/`, searching from the end of the prior range
+    Regex(Pattern),
+    /// `^/<regex>/`, searching from the start of the file
+    AnchoredRegex(Pattern),
+}
+
+#[derive(Clone)]
+pub(crate) struct Pattern(std::sync::Arc<syntect::parsing::Regex>);
+
+impl Pattern {
+    fn new(pattern: &str) -> anyhow::Result<Self> {
+        if let Some(err) = syntect::parsing::Regex::try_compile(pattern) {
+            anyhow::bail!("invalid regex `{pattern}`: {err}");
+        }
+        Ok(Self(std::sync::Arc::new(syntect::parsing::Regex::new(
+            pattern.to_owned(),
+        ))))
+    }
+
+    fn is_match(&self, line: &str) -> bool {
+        // `Regex::is_match` is anchored to the start of `line`
+        self.0.search(line, 0, line.len(), None)
+    }
+}
+
+impl std::fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.regex_str().fmt(f)
+    }
+}
+
+impl std::str::FromStr for LineRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(funcname) = s.strip_prefix(':') {
+            if funcname.is_empty() {
+                anyhow::bail!("missing function name in `{s}`");
+            }
+            return Ok(Self::Function(Pattern::new(funcname)?));
+        }
+
+        let (start, rest) = parse_bound(s)?;
+        let end = if rest.is_empty() {
+            Bound::Unset
+        } else if let Some(rest) = rest.strip_prefix(',') {
+            let (end, rest) = parse_bound(rest)?;
+            if !rest.is_empty() {
+                anyhow::bail!("unexpected `{rest}` in `{s}`");
+            }
+            end
+        } else {
+            anyhow::bail!("unexpected `{rest}` in `{s}`");
+        };
+        if matches!(start, Bound::Offset(_)) {
+            anyhow::bail!("`{s}` may only use an offset for the end of the range");
+        }
+        Ok(Self::Lines { start, end })
+    }
+}
+
+fn parse_bound(s: &str) -> anyhow::Result<(Bound, &str)> {
+    if let Some(rest) = s.strip_prefix("^/") {
+        let (pattern, rest) = split_regex(rest)?;
+        Ok((Bound::AnchoredRegex(Pattern::new(pattern)?), rest))
+    } else if let Some(rest) = s.strip_prefix('/') {
+        let (pattern, rest) = split_regex(rest)?;
+        Ok((Bound::Regex(Pattern::new(pattern)?), rest))
+    } else {
+        let end = s.find(',').unwrap_or(s.len());
+        let (bound, rest) = s.split_at(end);
+        let bound = if bound.is_empty() {
+            Bound::Unset
+        } else if let Some(offset) = bound.strip_prefix('+') {
+            Bound::Offset(parse_line_number(offset)? as isize)
+        } else if let Some(offset) = bound.strip_prefix('-') {
+            Bound::Offset(-(parse_line_number(offset)? as isize))
+        } else {
+            Bound::Line(parse_line_number(bound)?)
+        };
+        Ok((bound, rest))
+    }
+}
+
+fn parse_line_number(s: &str) -> anyhow::Result<usize> {
+    s.parse::<usize>()
+        .map_err(|_| anyhow::format_err!("invalid line number `{s}`"))
+}
+
+/// Split `<regex>/<rest>`, respecting escaped `/`
+fn split_regex(s: &str) -> anyhow::Result<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '/' if !escaped => return Ok((&s[..i], &s[i + 1..])),
+            _ => escaped = false,
+        }
+    }
+    anyhow::bail!("missing closing `/` for regex `{s}`")
+}
+
+impl LineRange {
+    /// Resolve to an inclusive, 1-based range of lines
+    ///
+    /// `prior_end` is the last line of the previous range, if any, for where to start searching
+    /// for a regex.
+    pub(crate) fn resolve(
+        &self,
+        lines: &[&str],
+        prior_end: usize,
+        syntax: &syntect::parsing::SyntaxReference,
+        syntax_set: &syntect::parsing::SyntaxSet,
+    ) -> anyhow::Result<(usize, usize)> {
+        let line_count = lines.len();
+        match self {
+            Self::Lines { start, end } => {
+                let start = match start {
+                    Bound::Unset => 1,
+                    Bound::Line(line) => *line,
+                    Bound::Offset(_) => unreachable!("rejected when parsing"),
+                    Bound::Regex(pattern) => search(lines, prior_end + 1, pattern)?,
+                    Bound::AnchoredRegex(pattern) => search(lines, 1, pattern)?,
+                };
+                if start == 0 || line_count < start {
+                    anyhow::bail!("file has only {line_count} lines");
+                }
+                let (start, end) = match end {
+                    Bound::Unset => (start, line_count),
+                    Bound::Line(line) => (start.min(*line), start.max(*line)),
+                    Bound::Offset(offset) if 0 <= *offset => {
+                        (start, start + (*offset as usize).max(1) - 1)
+                    }
+                    Bound::Offset(offset) => {
+                        let offset = offset.unsigned_abs().max(1);
+                        ((start + 1).saturating_sub(offset).max(1), start)
+                    }
+                    // Like git, the end can't match the start's line
+                    Bound::Regex(pattern) | Bound::AnchoredRegex(pattern) => {
+                        (start, search(lines, start + 1, pattern)?)
+                    }
+                };
+                Ok((start.max(1), end.min(line_count)))
+            }
+            Self::Function(pattern) => {
+                let definitions = function_definitions(lines, syntax, syntax_set)?;
+                let start = definitions
+                    .iter()
+                    .copied()
+                    .filter(|line| prior_end < *line)
+                    .find(|line| pattern.is_match(lines[*line - 1]))
+                    .ok_or_else(|| {
+                        anyhow::format_err!("no function found matching `{pattern:?}`")
+                    })?;
+                let end = definitions
+                    .iter()
+                    .copied()
+                    .find(|line| start < *line)
+                    .map(|line| line - 1)
+                    .unwrap_or(line_count);
+                Ok((start, end))
+            }
+        }
+    }
+}
+
+fn search(lines: &[&str], from: usize, pattern: &Pattern) -> anyhow::Result<usize> {
+    lines
+        .iter()
+        .enumerate()
+        .skip(from.saturating_sub(1))
+        .find(|(_, line)| pattern.is_match(line))
+        .map(|(i, _)| i + 1)
+        .ok_or_else(|| anyhow::format_err!("no match for regex `{pattern:?}`"))
+}
+
+/// Lines that define a function, according to the syntax's scopes
+fn function_definitions(
+    lines: &[&str],
+    syntax: &syntect::parsing::SyntaxReference,
+    syntax_set: &syntect::parsing::SyntaxSet,
+) -> anyhow::Result<Vec<usize>> {
+    let function =
+        syntect::parsing::Scope::new("entity.name.function").expect("hard-coded scope is valid");
+    let mut state = syntect::parsing::ParseState::new(syntax);
+    let mut stack = syntect::parsing::ScopeStack::new();
+    let mut definitions = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let mut is_definition = false;
+        let ops = state.parse_line(&format!("{line}\n"), syntax_set)?;
+        for (_, op) in ops {
+            stack.apply(&op)?;
+            is_definition |= stack
+                .as_slice()
+                .iter()
+                .any(|scope| function.is_prefix_of(*scope));
+        }
+        if is_definition {
+            definitions.push(i + 1);
+        }
+    }
+    Ok(definitions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resolve(range: &str, lines: &[&str]) -> (usize, usize) {
+        let syntax_set = crate::assets::integrated_syntaxes();
+        let syntax = syntax_set
+            .find_syntax_by_name("Rust")
+            .expect("always included");
+        range
+            .parse::<LineRange>()
+            .unwrap()
+            .resolve(lines, 0, syntax, &syntax_set)
+            .unwrap()
+    }
+
+    const LINES: &[&str] = &["fn one() {", "    1", "}", "", "fn two() {", "    2", "}"];
+
+    #[test]
+    fn numbers() {
+        assert_eq!(resolve("2,3", LINES), (2, 3));
+        assert_eq!(resolve("3,2", LINES), (2, 3));
+        assert_eq!(resolve("5", LINES), (5, 7));
+        assert_eq!(resolve(",2", LINES), (1, 2));
+        assert_eq!(resolve("2,+2", LINES), (2, 3));
+        assert_eq!(resolve("3,-2", LINES), (2, 3));
+        assert_eq!(resolve("6,+100", LINES), (6, 7));
+    }
+
+    #[test]
+    fn regex() {
+        assert_eq!(resolve("/two/,+3", LINES), (5, 7));
+        assert_eq!(resolve("/one/,/}/", LINES), (1, 3));
+        assert_eq!(resolve("/fn/,/fn/", LINES), (1, 5));
+    }
+
+    #[test]
+    fn funcname() {
+        assert_eq!(resolve(":one", LINES), (1, 4));
+        assert_eq!(resolve(":two", LINES), (5, 7));
+    }
+}
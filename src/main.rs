@@ -7,11 +7,28 @@ use proc_exit::prelude::*;
 
 mod args;
 mod assets;
+mod background;
 mod blame;
+mod color_depth;
 mod config;
+mod diff;
+mod explain;
+mod follow;
+mod format;
 mod git2_config;
 mod git_pager;
+mod granularity;
+mod gutter;
+mod hunks;
+mod line_range;
 mod logger;
+mod merge;
+mod origin;
+mod palette;
+mod pickaxe;
+mod syntax_mapping;
+mod timelapse;
+mod tui;
 
 use crate::git_pager::Pager;
 use crate::git2_config::Config;
@@ -30,6 +47,8 @@ fn run() -> proc_exit::ExitResult {
         git2_config::ColorWhen::Never => anstream::ColorChoice::Never,
     }
     .write_global();
+    // Like `color.ui`, this is about the terminal, so repo config doesn't apply
+    config.get(&color_depth::COLOR_DEPTH).write_global();
 
     // clap's `get_matches` uses Failure rather than Usage, so bypass it for `get_matches_safe`.
     let args = args::Args::parse();
@@ -58,6 +77,8 @@ fn run() -> proc_exit::ExitResult {
         list_languages(&mut config)?;
     } else if args.list_themes {
         list_themes(&mut config)?;
+    } else if let Some(action) = args.cache {
+        assets::cache(action, &config)?;
     } else if args.acknowledgements {
         use std::io::Write;
         let _ = writeln!(anstream::stdout(), "{}", assets::get_acknowledgements());
@@ -94,8 +115,20 @@ fn run() -> proc_exit::ExitResult {
             .info(CompileTimeInformation::default());
 
         report.print::<Markdown>();
+    } else if let Some(location) = args.explain.as_ref() {
+        explain::explain(location, &mut config, &args)?;
+    } else if let Some(file_path) = args.timelapse.as_deref() {
+        timelapse::timelapse(file_path, &mut config, &args)?;
     } else if let Some(file_path) = args.file.as_deref() {
-        blame::blame(file_path, &mut config, &args)?;
+        if args.history {
+            timelapse::history(file_path, &mut config, &args)?;
+        } else if let Some(pattern) =
+            pickaxe::Pattern::from_args(&args).with_code(proc_exit::Code::FAILURE)?
+        {
+            pickaxe::pickaxe(file_path, &pattern, &mut config, &args)?;
+        } else {
+            blame::blame(file_path, &mut config, &args)?;
+        }
     } else {
         unreachable!("clap ensured a mode exists");
     }
@@ -114,7 +147,7 @@ fn list_languages(config: &mut Config) -> proc_exit::ExitResult {
     let mut pager = pager.start();
     let pager = pager.as_writer().with_code(proc_exit::Code::FAILURE)?;
 
-    let syntax_set = assets::load_syntaxes();
+    let syntax_set = assets::load_syntaxes(config);
     let name_width = syntax_set
         .syntaxes()
         .iter()
@@ -164,9 +197,9 @@ fn list_themes(config: &mut Config) -> proc_exit::ExitResult {
     let mut pager = pager.start();
     let pager = pager.as_writer().with_code(proc_exit::Code::FAILURE)?;
 
-    let theme_set = assets::load_themes();
+    let theme_set = assets::load_themes(config);
     if colored_stdout {
-        let syntax_set = assets::load_syntaxes();
+        let syntax_set = assets::load_syntaxes(config);
         let syntax = syntax_set
             .find_syntax_by_name("Rust")
             .expect("always included");
@@ -0,0 +1,99 @@
+//! Find the pull request a commit was merged through
+
+/// Patterns for finding a pull request number in a commit message
+pub(crate) struct PullRequests {
+    /// Tried in order, using the first capture group if there is one, or else the whole match
+    patterns: Vec<syntect::parsing::Regex>,
+}
+
+impl PullRequests {
+    /// Recognize common hosts' merge messages, trying `custom` first
+    pub(crate) fn new(custom: Option<&str>) -> anyhow::Result<Self> {
+        const BUILTIN: &[&str] = &[
+            // GitHub merge commits
+            r"\AMerge pull request (#\d+)",
+            // GitLab merge commits
+            r"(?m)^See merge request [\w./-]*(![0-9]+)",
+            // GitHub squash merges
+            r"\A[^\n]*\((#\d+)\)\s*(?:\n|\z)",
+        ];
+        let mut patterns = Vec::new();
+        if let Some(custom) = custom {
+            if let Some(err) = syntect::parsing::Regex::try_compile(custom) {
+                anyhow::bail!("invalid `dive.pullRequestPattern` regex `{custom}`: {err}");
+            }
+            patterns.push(syntect::parsing::Regex::new(custom.to_owned()));
+        }
+        patterns.extend(
+            BUILTIN
+                .iter()
+                .map(|p| syntect::parsing::Regex::new((*p).to_owned())),
+        );
+        Ok(Self { patterns })
+    }
+
+    /// The pull request number in `message`, like `#123` or `!45`
+    pub(crate) fn find(&self, message: &str) -> Option<String> {
+        self.patterns.iter().find_map(|pattern| {
+            let mut region = syntect::parsing::Region::new();
+            if !pattern.search(message, 0, message.len(), Some(&mut region)) {
+                return None;
+            }
+            let (start, end) = region.pos(1).or_else(|| region.pos(0))?;
+            Some(message[start..end].to_owned())
+        })
+    }
+}
+
+/// Commits that `merge`, a commit on the first-parent history, brought in from other branches
+pub(crate) fn merged_by<'r>(
+    repo: &'r git2::Repository,
+    merge: &git2::Commit<'_>,
+) -> anyhow::Result<git2::Revwalk<'r>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::NONE)?;
+    for parent in merge.parent_ids().skip(1) {
+        revwalk.push(parent)?;
+    }
+    if let Ok(first_parent) = merge.parent_id(0) {
+        revwalk.hide(first_parent)?;
+    }
+    Ok(revwalk)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builtin() {
+        let prs = PullRequests::new(None).unwrap();
+        assert_eq!(
+            prs.find("Merge pull request #123 from user/branch\n\nFix it"),
+            Some("#123".to_owned())
+        );
+        assert_eq!(
+            prs.find(
+                "Merge branch 'fix' into 'main'\n\nFix it\n\nSee merge request group/project!45"
+            ),
+            Some("!45".to_owned())
+        );
+        assert_eq!(
+            prs.find("fix: Handle errors (#78)\n\n* Fix it"),
+            Some("#78".to_owned())
+        );
+        assert_eq!(prs.find("Fix it\n\nRefs (#78)"), None);
+        assert_eq!(prs.find("Fix #78"), None);
+    }
+
+    #[test]
+    fn custom() {
+        let prs = PullRequests::new(Some(r"Reviewed-on: .*/(\d+)")).unwrap();
+        assert_eq!(
+            prs.find("Fix it\n\nReviewed-on: https://review.example.com/4567"),
+            Some("4567".to_owned())
+        );
+        assert_eq!(prs.find("Fix it (#78)"), Some("#78".to_owned()));
+        assert!(PullRequests::new(Some("(")).is_err());
+    }
+}
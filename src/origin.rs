@@ -0,0 +1,236 @@
+//! Names for the commits lines came from
+
+/// How to name commits, from `dive.originStyle`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub(crate) enum OriginStyle {
+    /// Relative to the blamed revision, like `HEAD~10`
+    #[default]
+    Relative,
+    /// Relative to the nearest tag containing the commit, like `v1.2.0~3`
+    Describe,
+    /// Relative to any branch or tag containing the commit, like `main~5^2~1`
+    NameRev,
+    /// Abbreviated commit id
+    Sha,
+}
+
+impl OriginStyle {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Relative => "relative",
+            Self::Describe => "describe",
+            Self::NameRev => "name-rev",
+            Self::Sha => "sha",
+        }
+    }
+}
+
+impl std::fmt::Display for OriginStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl std::str::FromStr for OriginStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "relative" => Ok(Self::Relative),
+            "describe" => Ok(Self::Describe),
+            "name-rev" => Ok(Self::NameRev),
+            "sha" => Ok(Self::Sha),
+            _ => Err(anyhow::format_err!(
+                "unsupported originStyle value: `{s}`, expected one of `relative`, `describe`, `name-rev`, `sha`"
+            )),
+        }
+    }
+}
+
+impl crate::git2_config::Parseable for OriginStyle {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        <Self as std::str::FromStr>::from_str(s)
+    }
+}
+
+/// Name each of `targets` relative to a ref that contains it, like `git name-rev`
+///
+/// [`OriginStyle::Describe`] only considers tags while [`OriginStyle::NameRev`] also considers
+/// branches.  Names that go through fewer merges win, then those closer to the ref.  Commits no
+/// ref contains are left out, for callers to name some other way.
+pub(crate) fn name_revs(
+    repo: &git2::Repository,
+    style: OriginStyle,
+    targets: &std::collections::HashSet<git2::Oid>,
+) -> anyhow::Result<std::collections::HashMap<git2::Oid, String>> {
+    let mut names = std::collections::HashMap::new();
+    if targets.is_empty() {
+        return Ok(names);
+    }
+
+    // Like `git name-rev`, don't walk past commits well before every target, allowing for some
+    // clock skew
+    let cutoff = targets
+        .iter()
+        .filter_map(|id| repo.find_commit(*id).ok())
+        .map(|commit| commit.time().seconds())
+        .min()
+        .map(|oldest| oldest.saturating_sub(CUTOFF_DATE_SLOP))
+        .unwrap_or(i64::MIN);
+
+    let tips = ref_tips(repo, style)?;
+    let mut queue = std::collections::BinaryHeap::new();
+    for (i, (_, id)) in tips.iter().enumerate() {
+        queue.push(std::cmp::Reverse((0, 0, i, *id, Name::new(i))));
+    }
+
+    let mut visited = std::collections::HashSet::new();
+    while let Some(std::cmp::Reverse((merges, distance, _, id, name))) = queue.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        if targets.contains(&id) {
+            names.insert(id, name.render(&tips));
+            if names.len() == targets.len() {
+                break;
+            }
+        }
+        let commit = repo.find_commit(id)?;
+        if commit.time().seconds() < cutoff {
+            continue;
+        }
+        for (i, parent) in commit.parent_ids().enumerate() {
+            if visited.contains(&parent) {
+                continue;
+            }
+            let (merges, name) = if i == 0 {
+                (merges, name.first_parent())
+            } else {
+                (merges + 1, name.parent(i + 1))
+            };
+            queue.push(std::cmp::Reverse((
+                merges,
+                distance + 1,
+                name.tip,
+                parent,
+                name,
+            )));
+        }
+    }
+    Ok(names)
+}
+
+/// How far a commit's date may be off from its parents', matching `git name-rev`
+const CUTOFF_DATE_SLOP: i64 = 24 * 60 * 60;
+
+/// Commits that refs point to, with tags first so they win ties
+fn ref_tips(
+    repo: &git2::Repository,
+    style: OriginStyle,
+) -> anyhow::Result<Vec<(String, git2::Oid)>> {
+    let mut tags = Vec::new();
+    let mut branches = Vec::new();
+    for reference in repo.references()? {
+        let reference = reference?;
+        let (Some(name), Some(shorthand)) = (reference.name(), reference.shorthand()) else {
+            continue;
+        };
+        let tips = if name.starts_with("refs/tags/") {
+            &mut tags
+        } else if style == OriginStyle::NameRev
+            && (name.starts_with("refs/heads/") || name.starts_with("refs/remotes/"))
+        {
+            &mut branches
+        } else {
+            continue;
+        };
+        // Skip symbolic refs, like `origin/HEAD`, and tags of things other than commits
+        if reference.kind() != Some(git2::ReferenceType::Direct) {
+            continue;
+        }
+        let Ok(commit) = reference.peel_to_commit() else {
+            continue;
+        };
+        tips.push((shorthand.to_owned(), commit.id()));
+    }
+    tags.sort();
+    branches.sort();
+    tags.extend(branches);
+    Ok(tags)
+}
+
+/// A path from a ref tip, like `main~5^2~1`
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct Name {
+    /// Index into the tips
+    tip: usize,
+    /// Completed `~N^M` steps
+    path: String,
+    /// First parents taken since the last merge parent
+    generations: usize,
+}
+
+impl Name {
+    fn new(tip: usize) -> Self {
+        Self {
+            tip,
+            path: String::new(),
+            generations: 0,
+        }
+    }
+
+    fn first_parent(&self) -> Self {
+        Self {
+            generations: self.generations + 1,
+            ..self.clone()
+        }
+    }
+
+    /// Take the `nth` parent, 1-based like `^2`
+    fn parent(&self, nth: usize) -> Self {
+        let mut path = self.path.clone();
+        push_generations(&mut path, self.generations);
+        path.push_str(&format!("^{nth}"));
+        Self {
+            tip: self.tip,
+            path,
+            generations: 0,
+        }
+    }
+
+    fn render(&self, tips: &[(String, git2::Oid)]) -> String {
+        let mut name = tips[self.tip].0.clone();
+        name.push_str(&self.path);
+        push_generations(&mut name, self.generations);
+        name
+    }
+}
+
+fn push_generations(name: &mut String, generations: usize) {
+    if generations != 0 {
+        name.push_str(&format!("~{generations}"));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "name-rev".parse::<OriginStyle>().unwrap(),
+            OriginStyle::NameRev
+        );
+        assert!("nearest".parse::<OriginStyle>().is_err());
+    }
+
+    #[test]
+    fn name() {
+        let tips = [("main".to_owned(), git2::Oid::zero())];
+        let name = Name::new(0);
+        assert_eq!(name.render(&tips), "main");
+        let name = name.first_parent().first_parent().parent(2).first_parent();
+        assert_eq!(name.render(&tips), "main~2^2~1");
+    }
+}
@@ -0,0 +1,260 @@
+//! Gutter colors that distinguish the commits of a blame
+
+use crate::blame::Annotation;
+
+/// How to color the gutter, from `dive.colorBy`
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub(crate) enum ColorBy {
+    /// Newer commits are closer to the theme's accent color
+    Age,
+    /// Each commit gets its own color
+    Commit,
+    /// Each author gets their own color
+    Author,
+    /// Use the theme's gutter color for everything
+    #[default]
+    None,
+}
+
+impl ColorBy {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Age => "age",
+            Self::Commit => "commit",
+            Self::Author => "author",
+            Self::None => "none",
+        }
+    }
+}
+
+impl std::fmt::Display for ColorBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl std::str::FromStr for ColorBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "age" => Ok(Self::Age),
+            "commit" => Ok(Self::Commit),
+            "author" => Ok(Self::Author),
+            "none" => Ok(Self::None),
+            _ => Err(anyhow::format_err!(
+                "unsupported colorBy value: `{s}`, expected one of `age`, `commit`, `author`, `none`"
+            )),
+        }
+    }
+}
+
+impl crate::git2_config::Parseable for ColorBy {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        <Self as std::str::FromStr>::from_str(s)
+    }
+}
+
+/// Gutter style for each commit in a blame
+pub(crate) struct Palette {
+    styles: std::collections::HashMap<git2::Oid, anstyle::Style>,
+    default: anstyle::Style,
+}
+
+impl Palette {
+    /// Without color, everything is unstyled
+    pub(crate) fn plain() -> Self {
+        Self {
+            styles: Default::default(),
+            default: anstyle::Style::new(),
+        }
+    }
+
+    pub(crate) fn new<'a>(
+        color_by: ColorBy,
+        theme: &syntect::highlighting::Theme,
+        annotations: impl Iterator<Item = (git2::Oid, &'a Annotation)> + Clone,
+    ) -> Self {
+        let default = crate::blame::gutter_style(theme);
+        let styles = match color_by {
+            ColorBy::None => Default::default(),
+            ColorBy::Age => {
+                let times = annotations
+                    .clone()
+                    .map(|(_, a)| a.author().time().seconds());
+                let oldest = times.clone().min().unwrap_or(0);
+                let newest = times.max().unwrap_or(0);
+                // Comments are meant to fade into the background, like old code
+                let cold = theme
+                    .settings
+                    .gutter_foreground
+                    .or_else(|| scope_color(theme, "comment"));
+                let hot = scope_color(theme, "keyword").or(theme.settings.foreground);
+                annotations
+                    .map(|(id, a)| {
+                        let time = a.author().time().seconds();
+                        let heat = if newest == oldest {
+                            1.0
+                        } else {
+                            (time - oldest) as f32 / (newest - oldest) as f32
+                        };
+                        let blended = cold.zip(hot).and_then(|(c, h)| interpolate(c, h, heat));
+                        let style = match (blended, hot) {
+                            (Some(blended), _) => color_style(blended),
+                            // Without a gradient, at least call out the newer half
+                            (None, Some(hot)) if 0.5 <= heat => color_style(hot),
+                            _ => None,
+                        };
+                        (id, style.unwrap_or(default))
+                    })
+                    .collect()
+            }
+            ColorBy::Commit | ColorBy::Author => {
+                let colors = distinct_colors(theme);
+                annotations
+                    .map(|(id, a)| {
+                        let key = if color_by == ColorBy::Commit {
+                            id.as_bytes().to_vec()
+                        } else {
+                            a.author().email().as_bytes().to_vec()
+                        };
+                        let style = if colors.is_empty() {
+                            default
+                        } else {
+                            color_style(colors[stable_hash(&key) % colors.len()]).unwrap_or(default)
+                        };
+                        (id, style)
+                    })
+                    .collect()
+            }
+        };
+        Self { styles, default }
+    }
+
+    pub(crate) fn get(&self, id: git2::Oid) -> anstyle::Style {
+        self.styles.get(&id).copied().unwrap_or(self.default)
+    }
+}
+
+/// Styles for calling out `count` commits within lines of code
+///
+/// Each is a tint of the theme's background toward one of its code colors, falling back to an
+/// underline when the colors can't be blended.
+pub(crate) fn tints(theme: &syntect::highlighting::Theme, count: usize) -> Vec<anstyle::Style> {
+    const TINT: f32 = 0.3;
+    let underline = anstyle::Style::new().effects(anstyle::Effects::UNDERLINE);
+    let colors = distinct_colors(theme);
+    (0..count)
+        .map(|i| {
+            let tint = theme
+                .settings
+                .background
+                .zip(colors.get(i % colors.len().max(1)).copied())
+                .and_then(|(background, color)| interpolate(background, color, TINT))
+                .and_then(crate::assets::to_anstyle_color);
+            match tint {
+                Some(tint) => anstyle::Style::new().bg_color(Some(tint)),
+                None => underline,
+            }
+        })
+        .collect()
+}
+
+fn color_style(color: syntect::highlighting::Color) -> Option<anstyle::Style> {
+    crate::assets::to_anstyle_color(color).map(|c| anstyle::Style::new().fg_color(Some(c)))
+}
+
+/// Foreground the theme gives to `scope`, if it's different from the default
+fn scope_color(
+    theme: &syntect::highlighting::Theme,
+    scope: &str,
+) -> Option<syntect::highlighting::Color> {
+    let scope = syntect::parsing::Scope::new(scope).ok()?;
+    let highlighter = syntect::highlighting::Highlighter::new(theme);
+    let color = highlighter.style_mod_for_stack(&[scope]).foreground?;
+    (Some(color) != theme.settings.foreground).then_some(color)
+}
+
+/// Colors the theme uses for code, for telling commits apart
+fn distinct_colors(theme: &syntect::highlighting::Theme) -> Vec<syntect::highlighting::Color> {
+    const SCOPES: &[&str] = &[
+        "keyword",
+        "string",
+        "entity.name.function",
+        "storage.type",
+        "constant.numeric",
+        "entity.name.type",
+        "variable.parameter",
+        "support.function",
+    ];
+    let mut colors = Vec::new();
+    for scope in SCOPES {
+        if let Some(color) = scope_color(theme, scope) {
+            if !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+    }
+    colors
+}
+
+/// Blend from `cold` to `hot`, when both are true colors
+fn interpolate(
+    cold: syntect::highlighting::Color,
+    hot: syntect::highlighting::Color,
+    heat: f32,
+) -> Option<syntect::highlighting::Color> {
+    // Palette indices and the terminal's default can't be blended
+    if cold.a <= 1 || hot.a <= 1 {
+        return None;
+    }
+    let blend = |c: u8, h: u8| (f32::from(c) + (f32::from(h) - f32::from(c)) * heat).round() as u8;
+    Some(syntect::highlighting::Color {
+        r: blend(cold.r, hot.r),
+        g: blend(cold.g, hot.g),
+        b: blend(cold.b, hot.b),
+        a: 0xff,
+    })
+}
+
+/// FNV-1a, so colors don't change between runs or releases
+fn stable_hash(bytes: &[u8]) -> usize {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for b in bytes {
+        hash ^= u64::from(*b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash as usize
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const fn rgb(r: u8, g: u8, b: u8) -> syntect::highlighting::Color {
+        syntect::highlighting::Color { r, g, b, a: 0xff }
+    }
+
+    #[test]
+    fn gradient() {
+        let cold = rgb(0, 100, 200);
+        let hot = rgb(200, 100, 0);
+        assert_eq!(interpolate(cold, hot, 0.0), Some(cold));
+        assert_eq!(interpolate(cold, hot, 0.5), Some(rgb(100, 100, 100)));
+        assert_eq!(interpolate(cold, hot, 1.0), Some(hot));
+
+        let ansi = syntect::highlighting::Color {
+            r: 1,
+            g: 0,
+            b: 0,
+            a: 0,
+        };
+        assert_eq!(interpolate(ansi, hot, 0.5), None);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("age".parse::<ColorBy>().unwrap(), ColorBy::Age);
+        assert!("rainbow".parse::<ColorBy>().is_err());
+    }
+}
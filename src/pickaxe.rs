@@ -0,0 +1,247 @@
+//! Find when text was added to or removed from a file, like `git log -S`
+
+use proc_exit::WithCodeResultExt;
+
+use crate::diff::FileChange;
+use crate::git_pager::Pager;
+use crate::git2_config::Config;
+
+/// What to count occurrences of
+pub(crate) enum Pattern {
+    Text(String),
+    Regex(syntect::parsing::Regex),
+}
+
+impl Pattern {
+    /// The pattern from `-S` or `-G`
+    pub(crate) fn from_args(args: &crate::args::Args) -> anyhow::Result<Option<Self>> {
+        if let Some(text) = args.pickaxe.as_deref() {
+            if text.is_empty() {
+                anyhow::bail!("`-S` needs something to search for");
+            }
+            Ok(Some(Self::Text(text.to_owned())))
+        } else if let Some(regex) = args.pickaxe_regex.as_deref() {
+            if let Some(err) = syntect::parsing::Regex::try_compile(regex) {
+                anyhow::bail!("invalid `-G` regex `{regex}`: {err}");
+            }
+            Ok(Some(Self::Regex(syntect::parsing::Regex::new(
+                regex.to_owned(),
+            ))))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The text or regex, as given on the command line
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Text(text) => text,
+            Self::Regex(regex) => regex.regex_str(),
+        }
+    }
+
+    /// Non-overlapping occurrences in `text`
+    fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Text(needle) => text.matches(needle.as_str()).count(),
+            Self::Regex(regex) => {
+                let mut count = 0;
+                let mut pos = 0;
+                let mut region = syntect::parsing::Region::new();
+                while pos <= text.len() && regex.search(text, pos, text.len(), Some(&mut region)) {
+                    let Some((start, end)) = region.pos(0) else {
+                        break;
+                    };
+                    count += 1;
+                    // Step past empty matches so we don't find them again
+                    pos = if start == end {
+                        end + text[end..].chars().next().map(char::len_utf8).unwrap_or(1)
+                    } else {
+                        end
+                    };
+                }
+                count
+            }
+        }
+    }
+}
+
+pub(crate) fn pickaxe(
+    file_path: &std::path::Path,
+    pattern: &Pattern,
+    config: &mut Config,
+    args: &crate::args::Args,
+) -> proc_exit::ExitResult {
+    let colored_stdout =
+        anstream::AutoStream::choice(&std::io::stdout()) != anstream::ColorChoice::Never;
+
+    let cwd = std::env::current_dir().with_code(proc_exit::Code::FAILURE)?;
+    let repo = git2::Repository::discover(&cwd).with_code(proc_exit::Code::FAILURE)?;
+    config.add_repo(&repo);
+
+    let rel_path = crate::blame::to_repo_relative(&cwd, file_path, &repo)
+        .with_code(proc_exit::Code::FAILURE)?;
+
+    let head = args.rev.as_deref().unwrap_or("HEAD");
+    let found = search(&repo, head, &rel_path, pattern).with_code(proc_exit::Code::FAILURE)?;
+
+    if args.jump {
+        let syntax_set = crate::assets::load_syntaxes(config);
+        let theme_set = crate::assets::load_themes(config);
+        let blamer =
+            crate::blame::Blamer::new(&repo, &syntax_set, &theme_set, config, args, colored_stdout)
+                .with_code(proc_exit::Code::FAILURE)?;
+        let newest = found
+            .first()
+            .ok_or_else(|| {
+                anyhow::format_err!(
+                    "no commits changed the occurrences in {}",
+                    rel_path.display()
+                )
+            })
+            .with_code(proc_exit::Code::FAILURE)?;
+        if newest.added {
+            return Err(anyhow::format_err!(
+                "`{}` was introduced when {} was added in {}",
+                pattern.as_str(),
+                newest.old_path.display(),
+                newest.relative
+            ))
+            .with_code(proc_exit::Code::FAILURE);
+        }
+        let rev = crate::blame::parent_rev(&newest.relative);
+        let blamed = blamer
+            .blame(&rev, &newest.old_path, &args.line_range)
+            .with_code(proc_exit::Code::FAILURE)?;
+        // Start where the text was, if it was there before
+        let line = blamed
+            .lines()
+            .position(|line| 0 < pattern.count(line))
+            .map(|i| i + 1)
+            .unwrap_or(1);
+        return crate::blame::show(&blamer, blamed, line, config, args);
+    }
+
+    let origin_width = found
+        .iter()
+        .map(|f| crate::gutter::display_width(&f.relative))
+        .max()
+        .unwrap_or(0);
+    let counts = found
+        .iter()
+        .map(|f| format!("{} -> {}", f.old_count, f.new_count))
+        .collect::<Vec<_>>();
+    let counts_width = counts
+        .iter()
+        .map(|c| crate::gutter::display_width(c))
+        .max()
+        .unwrap_or(0);
+    let (origin_style, reset) = if colored_stdout {
+        (
+            anstyle::AnsiColor::Yellow.render_fg().to_string(),
+            anstyle::Reset.render().to_string(),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+
+    let pager = config.get(&crate::git2_config::PAGER);
+    let mut pager = Pager::stdout(&pager);
+    let mut pager = pager.start();
+    let pager = pager.as_writer().with_code(proc_exit::Code::FAILURE)?;
+    for (found, counts) in found.iter().zip(counts) {
+        let _ = writeln!(
+            pager,
+            "{origin_style}{}{reset} {} {} {}",
+            crate::gutter::pad(&found.relative, origin_width),
+            found.short,
+            crate::gutter::pad(&counts, counts_width),
+            found.summary
+        );
+    }
+
+    Ok(())
+}
+
+/// A commit that changed how often the pattern occurs
+struct Found {
+    /// Relative to where the history was walked from, like `HEAD~2`
+    relative: String,
+    short: String,
+    summary: String,
+    /// What the file was called before the commit
+    old_path: std::path::PathBuf,
+    old_count: usize,
+    new_count: usize,
+    /// Whether the commit added the file, leaving nothing before it to blame
+    added: bool,
+}
+
+/// Commits on the first-parent history of `head` that changed how often `pattern` occurs in
+/// `path`, newest first
+fn search(
+    repo: &git2::Repository,
+    head: &str,
+    path: &std::path::Path,
+    pattern: &Pattern,
+) -> anyhow::Result<Vec<Found>> {
+    let mut path = path.to_owned();
+    let mut found = Vec::new();
+    for entry in crate::blame::first_parent_history(repo, head)? {
+        let (relative, id) = entry?;
+        let commit = repo.find_commit(id)?;
+        if crate::diff::is_unchanged(&commit, &path)? {
+            continue;
+        }
+        let Some(change) = FileChange::new(repo, &commit, &path)? else {
+            break;
+        };
+        let old_path = change.old_path().to_owned();
+        let old = crate::blame::convert_file(change.old_content(), &old_path)?;
+        let new = crate::blame::convert_file(change.new_content(), &path)?;
+        let old_count = pattern.count(&old);
+        let new_count = pattern.count(&new);
+        if old_count != new_count {
+            let short = commit
+                .as_object()
+                .short_id()?
+                .as_str()
+                .expect("short_id is always valid UTF-8")
+                .to_owned();
+            let summary =
+                String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned();
+            found.push(Found {
+                relative,
+                short,
+                summary,
+                old_path: old_path.clone(),
+                old_count,
+                new_count,
+                added: change.is_added(),
+            });
+        }
+        if change.is_added() {
+            break;
+        }
+        path = old_path;
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn count() {
+        let text = Pattern::Text("foo".to_owned());
+        assert_eq!(text.count("foo(); foofoo"), 3);
+        assert_eq!(text.count("bar"), 0);
+
+        let regex = Pattern::Regex(syntect::parsing::Regex::new(r"fo+\(".to_owned()));
+        assert_eq!(regex.count("fo( foo( f("), 2);
+
+        let empty = Pattern::Regex(syntect::parsing::Regex::new("x*".to_owned()));
+        assert_eq!(empty.count("ab"), 3);
+    }
+}
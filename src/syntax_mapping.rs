@@ -0,0 +1,175 @@
+//! Choosing a syntax for a file
+
+use crate::git2_config::Config;
+use crate::git2_config::DefaultField;
+use crate::git2_config::RawField;
+
+/// Overrides for the syntax detected from a file's name or first line
+///
+/// From highest to lowest priority:
+/// - `--language`
+/// - The `dive-language` attribute
+/// - `dive.syntaxMap` entries, with later entries winning
+/// - The `linguist-language` attribute, since its names are GitHub's and might not match ours
+pub(crate) struct SyntaxMapping {
+    language: Option<String>,
+    rules: Vec<Rule>,
+}
+
+impl SyntaxMapping {
+    pub(crate) fn new(
+        syntax_set: &syntect::parsing::SyntaxSet,
+        config: &Config,
+        args: &crate::args::Args,
+    ) -> anyhow::Result<Self> {
+        if let Some(language) = args.language.as_deref() {
+            if syntax_set.find_syntax_by_token(language).is_none() {
+                anyhow::bail!("unknown language `{language}`, see `--list-languages`");
+            }
+        }
+        let rules = config
+            .get(&SYNTAX_MAP)
+            .iter()
+            .filter_map(|entry| match Rule::parse(entry) {
+                Ok(rule) => Some(rule),
+                Err(err) => {
+                    log::warn!("ignoring `dive.syntaxMap` entry `{entry}`: {err}");
+                    None
+                }
+            })
+            .collect();
+        Ok(Self {
+            language: args.language.clone(),
+            rules,
+        })
+    }
+
+    /// Pick a syntax for `path`, relative to the root of `repo`, with `file` as its contents
+    pub(crate) fn find<'s>(
+        &self,
+        repo: &git2::Repository,
+        syntax_set: &'s syntect::parsing::SyntaxSet,
+        path: &std::path::Path,
+        file: &str,
+    ) -> &'s syntect::parsing::SyntaxReference {
+        if let Some(language) = self.language.as_deref() {
+            return syntax_set
+                .find_syntax_by_token(language)
+                .expect("validated in `new`");
+        }
+        if let Some(language) = attribute(repo, path, DIVE_LANGUAGE) {
+            if let Some(syntax) = syntax_set.find_syntax_by_token(&language) {
+                return syntax;
+            }
+            log::warn!("unknown language `{language}` for `{}`", path.display());
+        }
+        if let Some(rule) = self.rules.iter().rev().find(|r| r.matches(path)) {
+            if let Some(syntax) = syntax_set.find_syntax_by_token(&rule.language) {
+                return syntax;
+            }
+            log::warn!("unknown language `{}` in `dive.syntaxMap`", rule.language);
+        }
+        if let Some(language) = attribute(repo, path, LINGUIST_LANGUAGE) {
+            if let Some(syntax) = syntax_set.find_syntax_by_token(&language) {
+                return syntax;
+            }
+            log::debug!("no syntax for linguist language `{language}`");
+        }
+        detect(syntax_set, path, file)
+    }
+}
+
+/// Pick a syntax by the file's name, falling back to its first line
+fn detect<'s>(
+    syntax_set: &'s syntect::parsing::SyntaxSet,
+    path: &std::path::Path,
+    file: &str,
+) -> &'s syntect::parsing::SyntaxReference {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let extension = path.extension().and_then(|x| x.to_str()).unwrap_or("");
+    syntax_set
+        .find_syntax_by_extension(file_name)
+        .or_else(|| syntax_set.find_syntax_by_extension(extension))
+        .or_else(|| {
+            let first_line = file.lines().next().unwrap_or("");
+            syntax_set.find_syntax_by_first_line(first_line)
+        })
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text())
+}
+
+fn attribute(repo: &git2::Repository, path: &std::path::Path, name: &str) -> Option<String> {
+    let value = repo
+        .get_attr(path, name, git2::AttrCheckFlags::default())
+        .ok()??;
+    match git2::AttrValue::from_string(Some(value)) {
+        git2::AttrValue::String(language) => Some(language.to_owned()),
+        _ => None,
+    }
+}
+
+/// A `dive.syntaxMap` entry, like `*.j2:Jinja2`
+struct Rule {
+    pattern: git2::Pathspec,
+    /// Patterns without a `/` match the file name in any directory, like in `.gitattributes`
+    file_name_only: bool,
+    language: String,
+}
+
+impl Rule {
+    fn parse(entry: &str) -> anyhow::Result<Self> {
+        let (pattern, language) = entry
+            .split_once(':')
+            .ok_or_else(|| anyhow::format_err!("expected `<pattern>:<language>`"))?;
+        let (pattern, language) = (pattern.trim(), language.trim());
+        if pattern.is_empty() || language.is_empty() {
+            anyhow::bail!("expected `<pattern>:<language>`");
+        }
+        let file_name_only = !pattern.contains('/');
+        let pattern = git2::Pathspec::new([pattern.trim_start_matches('/')])?;
+        Ok(Self {
+            pattern,
+            file_name_only,
+            language: language.to_owned(),
+        })
+    }
+
+    fn matches(&self, path: &std::path::Path) -> bool {
+        let path = if self.file_name_only {
+            let Some(file_name) = path.file_name() else {
+                return false;
+            };
+            std::path::Path::new(file_name)
+        } else {
+            path
+        };
+        self.pattern
+            .matches_path(path, git2::PathspecFlags::DEFAULT)
+    }
+}
+
+const DIVE_LANGUAGE: &str = "dive-language";
+const LINGUIST_LANGUAGE: &str = "linguist-language";
+
+pub(crate) const SYNTAX_MAP: DefaultField<Vec<String>> =
+    RawField::<Vec<String>>::new("dive.syntaxMap").default();
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rule() {
+        let rule = Rule::parse("*.conf.j2:Jinja2").unwrap();
+        assert_eq!(rule.language, "Jinja2");
+        assert!(rule.matches(std::path::Path::new("nginx.conf.j2")));
+        assert!(rule.matches(std::path::Path::new("deploy/nginx.conf.j2")));
+        assert!(!rule.matches(std::path::Path::new("nginx.conf")));
+
+        let rule = Rule::parse("ci/Jenkinsfile:Groovy").unwrap();
+        assert!(rule.matches(std::path::Path::new("ci/Jenkinsfile")));
+        assert!(!rule.matches(std::path::Path::new("Jenkinsfile")));
+
+        assert!(Rule::parse("Jenkinsfile").is_err());
+        assert!(Rule::parse(":Groovy").is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Step through every change to a file, like Perforce's Time-lapse View or `git log -L`
+
+use proc_exit::WithCodeResultExt;
+
+use crate::diff::FileChange;
+use crate::diff::Selection;
+use crate::git_pager::Pager;
+use crate::git2_config::Config;
+
+pub(crate) fn timelapse(
+    file_path: &std::path::Path,
+    config: &mut Config,
+    args: &crate::args::Args,
+) -> proc_exit::ExitResult {
+    show(file_path, "HEAD", &[], config, args)
+}
+
+/// Like `git log -L`, show each change to the lines selected with `-L`
+pub(crate) fn history(
+    file_path: &std::path::Path,
+    config: &mut Config,
+    args: &crate::args::Args,
+) -> proc_exit::ExitResult {
+    let head = args.rev.as_deref().unwrap_or("HEAD");
+    show(file_path, head, &args.line_range, config, args)
+}
+
+fn show(
+    file_path: &std::path::Path,
+    head: &str,
+    line_ranges: &[crate::line_range::LineRange],
+    config: &mut Config,
+    args: &crate::args::Args,
+) -> proc_exit::ExitResult {
+    let colored_stdout =
+        anstream::AutoStream::choice(&std::io::stdout()) != anstream::ColorChoice::Never;
+
+    let cwd = std::env::current_dir().with_code(proc_exit::Code::FAILURE)?;
+    let repo = git2::Repository::discover(&cwd).with_code(proc_exit::Code::FAILURE)?;
+    config.add_repo(&repo);
+    let interactive = args.interactive || config.get(&crate::blame::INTERACTIVE);
+
+    let rel_path = crate::blame::to_repo_relative(&cwd, file_path, &repo)
+        .with_code(proc_exit::Code::FAILURE)?;
+
+    let syntax_set = crate::assets::load_syntaxes(config);
+    let theme_set = crate::assets::load_themes(config);
+    let blamer =
+        crate::blame::Blamer::new(&repo, &syntax_set, &theme_set, config, args, colored_stdout)
+            .with_code(proc_exit::Code::FAILURE)?;
+    let ranges = blamer
+        .resolve_ranges(head, &rel_path, line_ranges)
+        .with_code(proc_exit::Code::FAILURE)?;
+
+    let revisions = changes(&repo, head, &rel_path, ranges).with_code(proc_exit::Code::FAILURE)?;
+    if revisions.is_empty() {
+        return Err(anyhow::format_err!(
+            "{} is not in {head}",
+            rel_path.display()
+        ))
+        .with_code(proc_exit::Code::FAILURE);
+    }
+
+    if interactive && crate::tui::is_available() {
+        return crate::tui::run_timelapse(&blamer, revisions).with_code(proc_exit::Code::FAILURE);
+    }
+
+    let pager = config.get(&crate::git2_config::PAGER);
+    let mut pager = Pager::stdout(&pager);
+    let mut pager = pager.start();
+    let pager = pager.as_writer().with_code(proc_exit::Code::FAILURE)?;
+    for (i, revision) in revisions.iter().enumerate() {
+        if i != 0 {
+            let _ = writeln!(pager);
+        }
+        let lines = revision
+            .render(&blamer)
+            .with_code(proc_exit::Code::FAILURE)?;
+        for line in lines {
+            let _ = writeln!(pager, "{line}");
+        }
+    }
+
+    Ok(())
+}
+
+/// A commit that changed the file
+pub(crate) struct Revision {
+    /// Relative to where the history was walked from, like `HEAD~2`
+    relative: String,
+    short: String,
+    summary: String,
+    author: String,
+    time: git2::Time,
+    change: FileChange,
+    selection: Selection,
+}
+
+impl Revision {
+    fn new(
+        relative: String,
+        commit: &git2::Commit<'_>,
+        change: FileChange,
+        selection: Selection,
+    ) -> anyhow::Result<Self> {
+        let short = commit
+            .as_object()
+            .short_id()?
+            .as_str()
+            .expect("short_id is always valid UTF-8")
+            .to_owned();
+        let summary =
+            String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned();
+        let author = commit.author();
+        let author = format!(
+            "{} <{}>",
+            String::from_utf8_lossy(author.name_bytes()),
+            String::from_utf8_lossy(author.email_bytes())
+        );
+        Ok(Self {
+            relative,
+            short,
+            summary,
+            author,
+            time: commit.author().when(),
+            change,
+            selection,
+        })
+    }
+
+    /// One line description, like `HEAD~2 1a2b3c4 Fix the thing`
+    pub(crate) fn title(&self) -> String {
+        format!("{} {} {}", self.relative, self.short, self.summary)
+    }
+
+    /// The commit's header followed by the highlighted diff
+    pub(crate) fn render(&self, blamer: &crate::blame::Blamer<'_>) -> anyhow::Result<Vec<String>> {
+        let title = self.title();
+        let title = if blamer.colored() {
+            format!(
+                "{}{title}{}",
+                anstyle::AnsiColor::Yellow.render_fg(),
+                anstyle::Reset.render()
+            )
+        } else {
+            title
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let mut lines = vec![
+            title,
+            format!(
+                "Author: {}, {} ({})",
+                self.author,
+                crate::gutter::format_date(self.time),
+                crate::gutter::format_age(self.time, now)
+            ),
+            String::new(),
+        ];
+        lines.extend(self.change.render(blamer, &self.selection)?);
+        Ok(lines)
+    }
+}
+
+/// Commits on the first-parent history of `head` that changed `ranges` of `path`, newest first
+///
+/// Renames are followed back to when the file was added, with `ranges` shifting to where the
+/// lines were in each version.  An empty `ranges` means the whole file.
+fn changes(
+    repo: &git2::Repository,
+    head: &str,
+    path: &std::path::Path,
+    mut ranges: Vec<(usize, usize)>,
+) -> anyhow::Result<Vec<Revision>> {
+    let whole_file = ranges.is_empty();
+    let mut path = path.to_owned();
+    let mut revisions = Vec::new();
+    for entry in crate::blame::first_parent_history(repo, head)? {
+        let (relative, id) = entry?;
+        let commit = repo.find_commit(id)?;
+        if crate::diff::is_unchanged(&commit, &path)? {
+            continue;
+        }
+        let Some(change) = FileChange::new(repo, &commit, &path)? else {
+            break;
+        };
+        let added = change.is_added();
+        let old_path = change.old_path().to_owned();
+        if whole_file {
+            if change.is_modified() || old_path != path {
+                revisions.push(Revision::new(relative, &commit, change, Selection::All)?);
+            }
+        } else {
+            let map = change.line_map()?;
+            let touched = ranges.iter().any(|(start, end)| map.changes(*start, *end));
+            let shown = ranges
+                .iter()
+                .map(|(start, end)| ((*start, *end), map.new_to_old_range(*start, *end)))
+                .collect::<Vec<_>>();
+            ranges = shown.iter().filter_map(|(_, old)| *old).collect();
+            ranges.sort_unstable();
+            if touched {
+                let selection = Selection::Lines(shown);
+                revisions.push(Revision::new(relative, &commit, change, selection)?);
+            }
+            if ranges.is_empty() {
+                // Every line was added here
+                break;
+            }
+        }
+        if added {
+            break;
+        }
+        path = old_path;
+    }
+    Ok(revisions)
+}
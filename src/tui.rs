@@ -0,0 +1,607 @@
+//! Interactive viewers for stepping back through history
+
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use is_terminal::IsTerminal as _;
+
+use crate::blame::Blamed;
+use crate::blame::Blamer;
+use crate::blame::Row;
+
+/// Whether we can take over the terminal
+pub(crate) fn is_available() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// Browse `blamed`, starting at `line`, until the user quits
+pub(crate) fn run(blamer: &Blamer<'_>, blamed: Blamed<'_>, line: usize) -> anyhow::Result<()> {
+    let _guard = TerminalGuard::new()?;
+    let mut viewer = Viewer::new(blamed, line);
+    let mut stdout = std::io::stdout().lock();
+    loop {
+        let (width, height) = crossterm::terminal::size()?;
+        viewer.draw(&mut stdout, blamer, width as usize, height as usize)?;
+        let page = (height as usize).saturating_sub(1).max(1);
+
+        let Event::Key(key) = crossterm::event::read()? else {
+            // Redraw on resize or anything else
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        if !viewer.on_key(blamer, key, page) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+struct Viewer<'r> {
+    history: History<View<'r>>,
+    /// Message for the status line, cleared on the next key press
+    status: Option<String>,
+}
+
+impl<'r> Viewer<'r> {
+    fn new(blamed: Blamed<'r>, line: usize) -> Self {
+        Self {
+            history: History::new(View::new(blamed, line)),
+            status: None,
+        }
+    }
+
+    /// Act on `key`, returning `false` when the user quits
+    fn on_key(&mut self, blamer: &Blamer<'r>, key: KeyEvent, page: usize) -> bool {
+        self.status = None;
+        let cursor = &mut self.history.current_mut().cursor;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('j') | KeyCode::Down => cursor.move_by(1),
+            KeyCode::Char('k') | KeyCode::Up => cursor.move_by(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => cursor.move_by(page as isize),
+            KeyCode::PageUp => cursor.move_by(-(page as isize)),
+            KeyCode::Char('g') | KeyCode::Home => cursor.move_to(1),
+            KeyCode::Char('G') | KeyCode::End => cursor.move_to(usize::MAX),
+            KeyCode::Enter | KeyCode::Char('b') => self.jump(blamer),
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => self.back(),
+            KeyCode::Char('l') | KeyCode::Right => self.forward(),
+            _ => {}
+        }
+        true
+    }
+
+    /// Blame what came before the line under the cursor
+    fn jump(&mut self, blamer: &Blamer<'r>) {
+        let view = self.history.current();
+        let result = view
+            .blamed
+            .parent_of(blamer.repo(), view.cursor.line)
+            .and_then(|(rev, path, line)| {
+                let blamed = blamer.blame(&rev, &path, &[])?;
+                Ok(View::new(blamed, line))
+            });
+        match result {
+            Ok(view) => self.history.push(view),
+            Err(err) => self.status = Some(err.to_string()),
+        }
+    }
+
+    fn back(&mut self) {
+        if !self.history.back() {
+            self.status = Some("already at the oldest view".to_owned());
+        }
+    }
+
+    fn forward(&mut self) {
+        if !self.history.forward() {
+            self.status = Some("already at the newest view".to_owned());
+        }
+    }
+
+    fn draw(
+        &mut self,
+        output: &mut impl std::io::Write,
+        blamer: &Blamer<'_>,
+        width: usize,
+        height: usize,
+    ) -> anyhow::Result<()> {
+        let body_height = height.saturating_sub(1);
+        let (position, history_len) = self.history.position();
+        let view = self.history.current_mut();
+        view.layout(blamer, width)?;
+        let rows = view
+            .rows
+            .as_ref()
+            .map(|(_, rows)| rows.as_slice())
+            .unwrap_or_default();
+        let cursor_row = rows
+            .iter()
+            .position(|r| r.line == view.cursor.line)
+            .unwrap_or(0);
+        view.cursor.scroll_to(cursor_row, body_height);
+        let top = view.cursor.top;
+
+        let reset = anstyle::Reset.render().to_string();
+
+        crossterm::queue!(
+            output,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
+        )?;
+        for (i, row) in rows.iter().skip(top).take(body_height).enumerate() {
+            let style = if top + i == cursor_row {
+                row.style.effects(anstyle::Effects::INVERT)
+            } else {
+                row.style
+            };
+            let style = style.render();
+            crossterm::queue!(output, crossterm::cursor::MoveTo(0, i as u16))?;
+            write!(output, "{style}{}{reset}{}{reset}", row.gutter, row.code)?;
+        }
+
+        let status = match &self.status {
+            Some(status) => status.clone(),
+            None => {
+                let origin = view
+                    .blamed
+                    .annotation(view.cursor.line)
+                    .map(|a| format!("  {} {}", a.origin(), a.summary()))
+                    .unwrap_or_default();
+                format!(
+                    "{}:{}  line {}/{}  [{position}/{history_len}]{origin}",
+                    view.blamed.rev,
+                    view.blamed.path.display(),
+                    view.cursor.line,
+                    view.cursor.line_count,
+                )
+            }
+        };
+        draw_status(output, &status, width, body_height)?;
+        output.flush()?;
+        Ok(())
+    }
+}
+
+/// Views visited, like a browser's history
+struct History<T> {
+    views: Vec<T>,
+    /// Index into `views` of the one being shown
+    current: usize,
+}
+
+impl<T> History<T> {
+    fn new(view: T) -> Self {
+        Self {
+            views: vec![view],
+            current: 0,
+        }
+    }
+
+    fn current(&self) -> &T {
+        &self.views[self.current]
+    }
+
+    fn current_mut(&mut self) -> &mut T {
+        &mut self.views[self.current]
+    }
+
+    /// Show `view`, discarding what was ahead of us like a browser does
+    fn push(&mut self, view: T) {
+        self.views.truncate(self.current + 1);
+        self.views.push(view);
+        self.current += 1;
+    }
+
+    /// Returns `false` when already at the first view
+    fn back(&mut self) -> bool {
+        if self.current == 0 {
+            false
+        } else {
+            self.current -= 1;
+            true
+        }
+    }
+
+    /// Returns `false` when already at the last view
+    fn forward(&mut self) -> bool {
+        if self.current + 1 == self.views.len() {
+            false
+        } else {
+            self.current += 1;
+            true
+        }
+    }
+
+    /// One-based position of the current view, along with how many there are
+    fn position(&self) -> (usize, usize) {
+        (self.current + 1, self.views.len())
+    }
+}
+
+/// A blame along with where the user is looking within it
+struct View<'r> {
+    blamed: Blamed<'r>,
+    cursor: Cursor,
+    /// Rows, cached for the terminal width they were laid out for
+    rows: Option<(usize, Vec<Row>)>,
+}
+
+impl<'r> View<'r> {
+    fn new(blamed: Blamed<'r>, line: usize) -> Self {
+        let cursor = Cursor::new(line, blamed.line_count());
+        Self {
+            blamed,
+            cursor,
+            rows: None,
+        }
+    }
+
+    fn layout(&mut self, blamer: &Blamer<'_>, width: usize) -> anyhow::Result<()> {
+        if self.rows.as_ref().map(|(w, _)| *w) != Some(width) {
+            // Lines can't run off the screen like they can in a pager
+            let wrap = match blamer.wrap() {
+                crate::blame::Overflow::None => crate::blame::Overflow::Truncate,
+                wrap => wrap,
+            };
+            let rows = self.blamed.render(blamer, width, wrap, |_| true)?;
+            self.rows = Some((width, rows));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Cursor {
+    /// Line in the file
+    line: usize,
+    line_count: usize,
+    /// First row shown
+    top: usize,
+}
+
+impl Cursor {
+    fn new(line: usize, line_count: usize) -> Self {
+        let line_count = line_count.max(1);
+        Self {
+            line: line.clamp(1, line_count),
+            line_count,
+            top: 0,
+        }
+    }
+
+    fn move_by(&mut self, offset: isize) {
+        let line = self.line.saturating_add_signed(offset);
+        self.move_to(line);
+    }
+
+    fn move_to(&mut self, line: usize) {
+        self.line = line.clamp(1, self.line_count);
+    }
+
+    /// Scroll just enough for `row`, the cursor's, to be among the `height` rows shown
+    fn scroll_to(&mut self, row: usize, height: usize) {
+        if row < self.top {
+            self.top = row;
+        } else if self.top + height <= row {
+            self.top = row + 1 - height;
+        }
+    }
+}
+
+/// Step through `revisions`, newest first, until the user quits
+pub(crate) fn run_timelapse(
+    blamer: &Blamer<'_>,
+    revisions: Vec<crate::timelapse::Revision>,
+) -> anyhow::Result<()> {
+    let _guard = TerminalGuard::new()?;
+    let mut viewer = Timelapse {
+        rendered: revisions.iter().map(|_| None).collect(),
+        revisions,
+        current: 0,
+        top: 0,
+        status: None,
+    };
+    let mut stdout = std::io::stdout().lock();
+    loop {
+        let (width, height) = crossterm::terminal::size()?;
+        viewer.draw(&mut stdout, blamer, width as usize, height as usize)?;
+        let page = (height as usize).saturating_sub(1).max(1);
+
+        let Event::Key(key) = crossterm::event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        viewer.status = None;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+            KeyCode::Char('j') | KeyCode::Down => viewer.scroll_by(1),
+            KeyCode::Char('k') | KeyCode::Up => viewer.scroll_by(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => viewer.scroll_by(page as isize),
+            KeyCode::PageUp => viewer.scroll_by(-(page as isize)),
+            KeyCode::Char('g') | KeyCode::Home => viewer.top = 0,
+            KeyCode::Char('G') | KeyCode::End => viewer.top = usize::MAX,
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Char('p') => viewer.older(),
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Char('n') => viewer.newer(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+struct Timelapse {
+    revisions: Vec<crate::timelapse::Revision>,
+    /// Lines for each revision, rendered when first shown
+    rendered: Vec<Option<Vec<String>>>,
+    /// Index into `revisions`, with `0` being the newest
+    current: usize,
+    /// First line shown
+    top: usize,
+    /// Message for the status line, cleared on the next key press
+    status: Option<String>,
+}
+
+impl Timelapse {
+    fn older(&mut self) {
+        if self.current + 1 == self.revisions.len() {
+            self.status = Some("already at the oldest revision".to_owned());
+        } else {
+            self.current += 1;
+            self.top = 0;
+        }
+    }
+
+    fn newer(&mut self) {
+        if self.current == 0 {
+            self.status = Some("already at the newest revision".to_owned());
+        } else {
+            self.current -= 1;
+            self.top = 0;
+        }
+    }
+
+    fn scroll_by(&mut self, offset: isize) {
+        self.top = self.top.saturating_add_signed(offset);
+    }
+
+    fn draw(
+        &mut self,
+        output: &mut impl std::io::Write,
+        blamer: &Blamer<'_>,
+        width: usize,
+        height: usize,
+    ) -> anyhow::Result<()> {
+        let body_height = height.saturating_sub(1);
+        let revision = &self.revisions[self.current];
+        let lines = match &mut self.rendered[self.current] {
+            Some(lines) => lines,
+            rendered => rendered.insert(revision.render(blamer)?),
+        };
+        self.top = self.top.min(lines.len().saturating_sub(body_height));
+
+        let reset = anstyle::Reset.render().to_string();
+
+        crossterm::queue!(
+            output,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
+        )?;
+        for (i, line) in lines.iter().skip(self.top).take(body_height).enumerate() {
+            crossterm::queue!(output, crossterm::cursor::MoveTo(0, i as u16))?;
+            write!(output, "{line}{reset}")?;
+        }
+
+        let status = match &self.status {
+            Some(status) => status.clone(),
+            None => format!(
+                "{}  [{}/{}]",
+                revision.title(),
+                self.revisions.len() - self.current,
+                self.revisions.len(),
+            ),
+        };
+        draw_status(output, &status, width, body_height)?;
+        output.flush()?;
+        Ok(())
+    }
+}
+
+fn draw_status(
+    output: &mut impl std::io::Write,
+    status: &str,
+    width: usize,
+    row: usize,
+) -> anyhow::Result<()> {
+    let status = crate::gutter::truncate(status, width);
+    let status_style = anstyle::Style::new().effects(anstyle::Effects::INVERT);
+    crossterm::queue!(output, crossterm::cursor::MoveTo(0, row as u16))?;
+    write!(
+        output,
+        "{}{}{}",
+        status_style.render(),
+        crate::gutter::pad(&status, width),
+        anstyle::Reset.render()
+    )?;
+    Ok(())
+}
+
+/// Restore the terminal, even on error
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> anyhow::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        let guard = Self;
+        crossterm::execute!(
+            std::io::stdout(),
+            crossterm::terminal::EnterAlternateScreen,
+            // Long lines are cut off at the edge rather than spilling onto the next row
+            crossterm::terminal::DisableLineWrap,
+            crossterm::cursor::Hide
+        )?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(
+            std::io::stdout(),
+            crossterm::cursor::Show,
+            crossterm::terminal::EnableLineWrap,
+            crossterm::terminal::LeaveAlternateScreen
+        );
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn history() {
+        let mut history = History::new("HEAD");
+        assert!(!history.back());
+        assert!(!history.forward());
+
+        history.push("HEAD~1");
+        history.push("HEAD~2");
+        assert_eq!(history.position(), (3, 3));
+        assert!(history.back());
+        assert!(history.back());
+        assert!(!history.back());
+        assert_eq!(*history.current(), "HEAD");
+        assert!(history.forward());
+        assert_eq!(*history.current(), "HEAD~1");
+
+        // Jumping from the middle drops what was ahead
+        history.push("v1.0");
+        assert_eq!(history.position(), (3, 3));
+        assert!(!history.forward());
+        assert!(history.back());
+        assert_eq!(*history.current(), "HEAD~1");
+    }
+
+    #[test]
+    fn cursor() {
+        let mut cursor = Cursor::new(100, 10);
+        assert_eq!(cursor.line, 10);
+        cursor.move_by(-3);
+        assert_eq!(cursor.line, 7);
+        cursor.move_by(-100);
+        assert_eq!(cursor.line, 1);
+        cursor.move_to(usize::MAX);
+        assert_eq!(cursor.line, 10);
+
+        // Empty files still have a line to sit on
+        let cursor = Cursor::new(0, 0);
+        assert_eq!((cursor.line, cursor.line_count), (1, 1));
+
+        let mut cursor = Cursor::new(1, 10);
+        cursor.scroll_to(4, 5);
+        assert_eq!(cursor.top, 0);
+        cursor.scroll_to(7, 5);
+        assert_eq!(cursor.top, 3);
+        cursor.scroll_to(5, 5);
+        assert_eq!(cursor.top, 3);
+        cursor.scroll_to(1, 5);
+        assert_eq!(cursor.top, 1);
+    }
+
+    #[test]
+    fn jump() {
+        use clap::Parser as _;
+
+        let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+        let root_path = root.path().unwrap();
+        let plan = git_fixture::TodoList {
+            commands: vec![
+                git_fixture::Command::Tree(git_fixture::Tree {
+                    files: [("file.txt", "one\n")]
+                        .into_iter()
+                        .map(|(p, c)| (p.into(), c.into()))
+                        .collect::<std::collections::HashMap<_, _>>(),
+                    message: Some("A".to_owned()),
+                    author: None,
+                }),
+                git_fixture::Command::Tree(git_fixture::Tree {
+                    files: [("file.txt", "one\ntwo\n")]
+                        .into_iter()
+                        .map(|(p, c)| (p.into(), c.into()))
+                        .collect::<std::collections::HashMap<_, _>>(),
+                    message: Some("B".to_owned()),
+                    author: None,
+                }),
+                git_fixture::Command::Branch("main".into()),
+            ],
+            ..Default::default()
+        };
+        plan.run(root_path).unwrap();
+        std::fs::write(root_path.join("file.txt"), "one\ntwo\nthree\n").unwrap();
+
+        let repo = git2::Repository::open(root_path).unwrap();
+        let mut config = crate::git2_config::Config::system();
+        config.add_repo(&repo);
+        let args = crate::args::Args::parse_from(["git-dive", "file.txt"]);
+        let syntax_set = crate::assets::integrated_syntaxes();
+        let theme_set = crate::assets::load_themes(&config);
+        let blamer = Blamer::new(&repo, &syntax_set, &theme_set, &config, &args, false).unwrap();
+        let path = std::path::Path::new("file.txt");
+        let blamed = blamer.blame_args(&args, path).unwrap();
+
+        let summary = |viewer: &Viewer<'_>| {
+            let view = viewer.history.current();
+            view.blamed
+                .annotation(view.cursor.line)
+                .unwrap()
+                .summary()
+                .to_owned()
+        };
+
+        // A line added in the working tree lands near where it would be in `HEAD`
+        let mut viewer = Viewer::new(blamed, 3);
+        viewer.jump(&blamer);
+        assert_eq!(viewer.status, None);
+        assert_eq!(viewer.history.current().blamed.rev, "HEAD");
+        assert_eq!(viewer.history.current().cursor.line, 2);
+        assert_eq!(summary(&viewer), "B");
+
+        viewer.jump(&blamer);
+        assert_eq!(viewer.status, None);
+        assert_eq!(viewer.history.position(), (3, 3));
+        assert_eq!(viewer.history.current().cursor.line, 1);
+        assert_eq!(summary(&viewer), "A");
+
+        // Nothing came before the root commit
+        viewer.jump(&blamer);
+        assert!(
+            viewer
+                .status
+                .as_deref()
+                .unwrap()
+                .ends_with("is a root commit"),
+            "{:?}",
+            viewer.status
+        );
+        assert_eq!(viewer.history.position(), (3, 3));
+
+        viewer.back();
+        viewer.back();
+        assert_eq!(viewer.history.position(), (1, 3));
+        viewer.back();
+        assert_eq!(viewer.status.as_deref(), Some("already at the oldest view"));
+        viewer.forward();
+        viewer.forward();
+        viewer.forward();
+        assert_eq!(viewer.status.as_deref(), Some("already at the newest view"));
+        assert_eq!(summary(&viewer), "A");
+
+        root.close().unwrap();
+    }
+}
@@ -72,3 +72,1639 @@ fn js_highlight_panics() {
 
     root.close().unwrap();
 }
+
+#[test]
+fn reverse() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nguard();\nrun();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nguard();\nrun();\nstop();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun();\nstop();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("C".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--reverse")
+        .arg("HEAD~2..")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD   1 │ init();
+HEAD~1 2 │ guard();
+HEAD   3 │ run();
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn next() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nguard();\nrun();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nguard();\nrun(1);\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun(1);\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("C".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--next")
+        .arg("HEAD~2")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~2          1 │ init();
+⋮      → HEAD   2 │ guard();
+⋮      → HEAD~1 3 │ run();
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn origin_style() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tag("v1.0".into()),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun();\nstop();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("C".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("basic.js")
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", "'dive.originStyle'='describe'")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+v1.0~1 1 │ init();
+v1.0   2 │ run();
+HEAD   3 │ stop();
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("basic.js")
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", "'dive.originStyle'='name-rev'")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+v1.0~1 1 │ init();
+v1.0   2 │ run();
+main   3 │ stop();
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn origin_style_granularity() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init(a);\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init(a);\nrun();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tag("v1.0".into()),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init(b);\nrun();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("C".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tag("v2".into()),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    // Naming the commits of tokens shouldn't rename the commits of lines
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .args(["--granularity", "word", "basic.js"])
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", "'dive.originStyle'='describe'")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+v2     1 │ init(b);
+v1.0   2 │ run();
+"
+            .raw(),
+        );
+
+    let output = snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .args(["--granularity", "word", "--format", "json", "basic.js"])
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", "'dive.originStyle'='describe'")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains(r#""origin": "v1.0~1""#), "{output}");
+    assert!(!output.contains(r#""origin": "HEAD"#), "{output}");
+
+    let output = snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .args(["--granularity", "word", "basic.js"])
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", "'dive.originStyle'='sha'")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    assert!(!output.contains("HEAD"), "{output}");
+
+    root.close().unwrap();
+}
+
+#[test]
+fn ignore_rev() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init(\"a\");\nrun('b');\nstop(\"c\");\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Reformat".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--ignore-rev")
+        .arg("HEAD")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .env(
+            "GIT_CONFIG_PARAMETERS",
+            "'blame.markIgnoredLines'='true' 'blame.markUnblamableLines'='true'",
+        )
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+?HEAD~1 1 │ init(\"a\");
+HEAD~1  2 │ run('b');
+*HEAD   3 │ stop(\"c\");
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn gutter() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Añadir".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Run".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("basic.js")
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", "'dive.gutter'='summary,origin'")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+Añadir HEAD~1 1 │ init('a');
+Run    HEAD   2 │ run('b');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn worktree() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+    std::fs::write(
+        root_path.join("basic.js"),
+        "init('a');\nrun('c');\nstop();\n",
+    )
+    .unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD     1 │ init('a');
+WORKTREE 2 │ run('c');
+⋮        3 │ stop();
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--cached")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD 1 │ init('a');
+⋮    2 │ run('b');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn follow() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("old.js", "init('a');\nrun('b');\nstop('c');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("new.js", "init('a');\nrun('b');\nstop('c');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Rename".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("new.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~1 old.js 1 │ init('a');
+⋮             2 │ run('b');
+⋮             3 │ stop('c');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--follow")
+        .arg("HEAD~1")
+        .arg("new.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~1 1 │ init('a');
+⋮      2 │ run('b');
+⋮      3 │ stop('c');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn wrap() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [(
+                    "basic.js",
+                    "fetch('https://example.com/a/very/long/path/to/index.html');\n",
+                )]
+                .into_iter()
+                .map(|(p, c)| (p.into(), c.into()))
+                .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("basic.js")
+        .current_dir(root_path)
+        .env("COLUMNS", "40")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD 1 │ fetch('https://example.com/a/
+⋮    ⋮ │ very/long/path/to/index.html');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("basic.js")
+        .current_dir(root_path)
+        .env("COLUMNS", "40")
+        .env("GIT_CONFIG_PARAMETERS", "'dive.wrap'='truncate'")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD 1 │ fetch('https://example.com/a/
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn whitespace() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\n  run('b');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Indent".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~1 1 │ init('a');
+⋮      2 │   run('b');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--no-ignore-whitespace")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~1 1 │ init('a');
+HEAD   2 │   run('b');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn pull_request() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Merge pull request #12 from user/init".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Run (#13)".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\nstop('c');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Stop".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("basic.js")
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", "'dive.gutter'='origin,pr'")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~2 #12 1 │ init('a');
+HEAD~1 #13 2 │ run('b');
+HEAD       3 │ stop('c');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn explain() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nstop('c');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Init".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\nstop('c');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some(
+                    "Run (#13)\n\nRun before stopping.\n\nSigned-off-by: Someone <someone@example.com>\n"
+                        .to_owned(),
+                ),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\nstop('c');\nexit();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Exit".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--explain")
+        .arg("basic.js:2")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+commit [..] (HEAD~1)
+Author: [..]
+Date:   [..]
+Pull-Request: #13
+
+    Run (#13)
+
+    Run before stopping.
+
+Trailers:
+    Signed-off-by: Someone <someone@example.com>
+
+diff --git a/basic.js b/basic.js
+--- a/basic.js
++++ b/basic.js
+@@ -1,2 +1,3 @@
+ init('a');
++run('b');
+ stop('c');
+",
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn timelapse() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Init".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\n"), ("other.js", "other();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Other".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [
+                    ("basic.js", "init('a');\nrun('b');\n"),
+                    ("other.js", "other();\n"),
+                ]
+                .into_iter()
+                .map(|(p, c)| (p.into(), c.into()))
+                .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Run".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--timelapse")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD [..] Run
+Author: [..]
+
+diff --git a/basic.js b/basic.js
+--- a/basic.js
++++ b/basic.js
+@@ -1 +1,2 @@
+ init('a');
++run('b');
+
+HEAD~2 [..] Init
+Author: [..]
+
+diff --git a/basic.js b/basic.js
+--- /dev/null
++++ b/basic.js
+@@ -0,0 +1 @@
++init('a');
+",
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    // The history always starts from `HEAD`
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .args(["HEAD~1", "basic.js", "--timelapse", "basic.js"])
+        .current_dir(root_path)
+        .assert()
+        .failure()
+        .stderr_eq(
+            "\
+error: the argument '[REV]' cannot be used with '--timelapse <FILE>'
+...
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn history() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\nstop('c');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Init".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('B');\nstop('c');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Run".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [(
+                    "renamed.js",
+                    "setup();\ninit('a');\nrun('B');\nstop('c');\n",
+                )]
+                .into_iter()
+                .map(|(p, c)| (p.into(), c.into()))
+                .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Setup".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [(
+                    "renamed.js",
+                    "setup();\ninit('a');\nrun('B');\nstop('C');\n",
+                )]
+                .into_iter()
+                .map(|(p, c)| (p.into(), c.into()))
+                .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Stop".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--history")
+        .arg("-L3,3")
+        .arg("renamed.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~2 [..] Run
+Author: [..]
+
+diff --git a/basic.js b/basic.js
+--- a/basic.js
++++ b/basic.js
+@@ -2,1 +2,1 @@
+-run('b');
++run('B');
+
+HEAD~3 [..] Init
+Author: [..]
+
+diff --git a/basic.js b/basic.js
+--- /dev/null
++++ b/basic.js
+@@ -0,0 +2,1 @@
++run('b');
+",
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn pickaxe() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Init".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('b');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Run".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\nrun('B');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Shout".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init('a');\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("Stop running".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("-Srun(")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD   [..] 1 -> 0 Stop running
+HEAD~2 [..] 0 -> 1 Run
+",
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("-G")
+        .arg(r"\('[a-z]'\)")
+        .arg("--jump")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~3 1 │ init('a');
+HEAD~2 2 │ run('b');
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    // Nothing came before the commit that added the file
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("-Sinit(")
+        .arg("--jump")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .failure()
+        .stdout_eq(
+            "\
+",
+        )
+        .stderr_eq(
+            "\
+`init(` was introduced when basic.js was added in HEAD~3
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn custom_syntax() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("rules.mydsl", "rule a when b\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+    let syntaxes = root_path.join(".syntaxes");
+    std::fs::create_dir_all(&syntaxes).unwrap();
+    std::fs::write(
+        syntaxes.join("mydsl.sublime-syntax"),
+        "\
+%YAML 1.2
+---
+name: MyDSL
+file_extensions: [mydsl]
+scope: source.mydsl
+contexts:
+  main:
+    - match: '\\b(rule|when)\\b'
+      scope: keyword.control.mydsl
+",
+    )
+    .unwrap();
+    let config = format!("'dive.syntaxesDir'='{}'", syntaxes.display());
+
+    let output = snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--list-languages")
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", &config)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("MyDSL"), "{output}");
+
+    // Without the syntax, the whole line would be highlighted as one plain-text region
+    let output = snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("rules.mydsl")
+        .current_dir(root_path)
+        .env("GIT_CONFIG_PARAMETERS", &config)
+        .env("CLICOLOR_FORCE", "1")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("rule\u{1b}[0m"), "{output:?}");
+
+    root.close().unwrap();
+}
+
+#[test]
+fn theme_auto() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("a.txt", "a\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+    let dump = |colorfgbg: Option<&str>, config: &str| {
+        let mut cmd = snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+            .args(["--dump-config", "-"])
+            .current_dir(root_path)
+            .env("GIT_CONFIG_PARAMETERS", config)
+            .env_remove("COLORFGBG");
+        if let Some(colorfgbg) = colorfgbg {
+            cmd = cmd.env("COLORFGBG", colorfgbg);
+        }
+        let output = cmd.assert().success().get_output().stdout.clone();
+        let output = String::from_utf8(output).unwrap();
+        output.lines().last().unwrap().to_owned()
+    };
+
+    assert_eq!(
+        dump(None, ""),
+        "# Using theme `Monokai Extended`: assuming a dark background"
+    );
+    assert_eq!(
+        dump(Some("0;15"), ""),
+        "# Using theme `Monokai Extended Light`: `COLORFGBG` is for a light background"
+    );
+    assert_eq!(
+        dump(Some("15;0"), "'dive.theme.dark'='Nord'"),
+        "# Using theme `Nord`: `COLORFGBG` is for a dark background"
+    );
+    assert_eq!(
+        dump(Some("0;15"), "'dive.theme'='Nord'"),
+        "# Using theme `Nord`: set by `dive.theme`"
+    );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn color_depth() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("main.rs", "fn main() {}\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+    let blame = |depth: &str| {
+        let output = snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+            .arg("main.rs")
+            .current_dir(root_path)
+            .env("GIT_CONFIG_PARAMETERS", format!("'dive.colorDepth'='{depth}'"))
+            .env("CLICOLOR_FORCE", "1")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    let output = blame("truecolor");
+    assert!(output.contains("\u{1b}[38;2;"), "{output:?}");
+    let output = blame("256");
+    assert!(!output.contains("\u{1b}[38;2;"), "{output:?}");
+    assert!(output.contains("\u{1b}[38;5;"), "{output:?}");
+    let output = blame("16");
+    assert!(!output.contains("\u{1b}[38;"), "{output:?}");
+
+    root.close().unwrap();
+}
+
+#[test]
+fn syntax_map() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("Jenkinsfile", "fn main() {}\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+    let blame = |args: &[&str], config: &str| {
+        let output = snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+            .args(args)
+            .arg("Jenkinsfile")
+            .current_dir(root_path)
+            .env("GIT_CONFIG_PARAMETERS", config)
+            .env("CLICOLOR_FORCE", "1")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    let detected = blame(&[], "");
+    let language = blame(&["--language", "Rust"], "");
+    assert_ne!(detected, language);
+    assert_eq!(blame(&[], "'dive.syntaxMap'='Jenkins*:Rust'"), language);
+    assert_eq!(blame(&["--language", "rs"], ""), language);
+
+    std::fs::write(
+        root_path.join(".gitattributes"),
+        "Jenkinsfile linguist-language=Rust\n",
+    )
+    .unwrap();
+    assert_eq!(blame(&[], ""), language);
+    // `dive.syntaxMap` takes priority over GitHub's names
+    let plain = blame(&["--language", "Plain Text"], "");
+    assert_eq!(blame(&[], "'dive.syntaxMap'='Jenkins*:Plain Text'"), plain);
+
+    std::fs::write(
+        root_path.join(".gitattributes"),
+        "Jenkinsfile linguist-language=Rust dive-language=txt\n",
+    )
+    .unwrap();
+    assert_eq!(blame(&[], ""), plain);
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .args(["--language", "Nope", "Jenkinsfile"])
+        .current_dir(root_path)
+        .assert()
+        .failure()
+        .stderr_eq(
+            "\
+unknown language `Nope`, see `--list-languages`
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn cache() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let syntaxes = root_path.join("syntaxes");
+    std::fs::create_dir_all(&syntaxes).unwrap();
+    std::fs::write(
+        syntaxes.join("mydsl.sublime-syntax"),
+        "\
+%YAML 1.2
+---
+name: MyDSL
+file_extensions: [mydsl]
+scope: source.mydsl
+contexts:
+  main:
+    - match: '\\b(rule|when)\\b'
+      scope: keyword.control.mydsl
+",
+    )
+    .unwrap();
+    let config = format!("'dive.syntaxesDir'='{}'", syntaxes.display());
+    let cache_home = root_path.join("cache");
+    let run = |action: &str| {
+        snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+            .arg("--cache")
+            .arg(action)
+            .current_dir(root_path)
+            .env("GIT_CONFIG_PARAMETERS", &config)
+            .env("XDG_CACHE_HOME", &cache_home)
+            .env("XDG_CONFIG_HOME", root_path.join("config"))
+            .assert()
+            .success()
+    };
+
+    run("info").stdout_eq(
+        "\
+Cache: [..]
+Syntaxes: 1 in [..] (not cached, run `git dive --cache build`)
+Themes: 0 in [..] (nothing to cache)
+",
+    );
+    run("build").stdout_eq(
+        "\
+Cached 1 syntaxes and 0 themes in [..]
+",
+    );
+    run("info").stdout_eq(
+        "\
+Cache: [..]
+Syntaxes: 1 in [..] (cached)
+Themes: 0 in [..] (nothing to cache)
+",
+    );
+
+    // Changing a syntax invalidates the cache
+    std::fs::write(
+        syntaxes.join("other.sublime-syntax"),
+        "\
+%YAML 1.2
+---
+name: Other
+file_extensions: [other]
+scope: source.other
+contexts:
+  main: []
+",
+    )
+    .unwrap();
+    run("info").stdout_eq(
+        "\
+Cache: [..]
+Syntaxes: 2 in [..] (not cached, run `git dive --cache build`)
+Themes: 0 in [..] (nothing to cache)
+",
+    );
+
+    run("clear").stdout_eq(
+        "\
+Removed [..]
+",
+    );
+    assert!(!cache_home.join("git-dive").exists());
+
+    root.close().unwrap();
+}
+
+#[test]
+fn line_range() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("main.rs", "fn one() {\n    1\n}\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("main.rs", "fn one() {\n    1\n}\n\nfn two() {\n    2\n}\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    // The end regex is searched for after the start's line, like in git
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("-L/fn/,/fn/")
+        .arg("main.rs")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD~1 1 │ fn one() {
+⋮      2 │     1
+⋮      3 │ }
+HEAD   4 │ 
+⋮      5 │ fn two() {
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("-L:two")
+        .arg("main.rs")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD 5 │ fn two() {
+⋮    6 │     2
+⋮    7 │ }
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn overlapping_line_ranges() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("f.txt", "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    // Nested, overlapping, and adjacent ranges are merged, like in git
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .args(["-L2,6", "-L3,4", "-L7,8", "-L11,+2", "f.txt"])
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+HEAD  2 │ 2
+⋮     3 │ 3
+⋮     4 │ 4
+⋮     5 │ 5
+⋮     6 │ 6
+⋮     7 │ 7
+⋮     8 │ 8
+HEAD 11 │ 11
+⋮    12 │ 12
+"
+            .raw(),
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .args(["-L1,12", "-L5,10", "--format", "json", "f.txt"])
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn color_by() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("main.rs", "one();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("main.rs", "one();\ntwo();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        // Commits need different times to be told apart by age
+        sleep: Some(std::time::Duration::from_millis(1100)),
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+    let blame = |color_by: &str| {
+        snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+            .arg("main.rs")
+            .current_dir(root_path)
+            .env(
+                "GIT_CONFIG_PARAMETERS",
+                format!(
+                    "'dive.colorBy'='{color_by}' 'dive.theme'='Monokai Extended' 'dive.colorDepth'='truecolor'"
+                ),
+            )
+            .env("CLICOLOR_FORCE", "1")
+            .assert()
+            .success()
+    };
+
+    blame("none").stdout_eq(
+        "\
+\u{1b}[38;5;238mHEAD~1 1 │ \u{1b}[0m\u{1b}[38;2;102;217;239mone\u{1b}[0m\u{1b}[38;2;248;248;242m(\u{1b}[0m\u{1b}[38;2;248;248;242m)\u{1b}[0m\u{1b}[38;2;248;248;242m;\u{1b}[0m
+\u{1b}[0m\u{1b}[38;5;238mHEAD   2 │ \u{1b}[0m\u{1b}[38;2;102;217;239mtwo\u{1b}[0m\u{1b}[38;2;248;248;242m(\u{1b}[0m\u{1b}[38;2;248;248;242m)\u{1b}[0m\u{1b}[38;2;248;248;242m;\u{1b}[0m
+\u{1b}[0m"
+        .raw(),
+    );
+    // Older changes fade into the background and newer ones stand out
+    blame("age").stdout_eq(
+        "\
+\u{1b}[38;2;117;113;94mHEAD~1 1 │ \u{1b}[0m\u{1b}[38;2;102;217;239mone\u{1b}[0m\u{1b}[38;2;248;248;242m(\u{1b}[0m\u{1b}[38;2;248;248;242m)\u{1b}[0m\u{1b}[38;2;248;248;242m;\u{1b}[0m
+\u{1b}[0m\u{1b}[38;2;249;38;114mHEAD   2 │ \u{1b}[0m\u{1b}[38;2;102;217;239mtwo\u{1b}[0m\u{1b}[38;2;248;248;242m(\u{1b}[0m\u{1b}[38;2;248;248;242m)\u{1b}[0m\u{1b}[38;2;248;248;242m;\u{1b}[0m
+\u{1b}[0m"
+        .raw(),
+    );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn dump_config() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("main.rs", "fn main() {}\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .args(["--dump-config", "-"])
+        .current_dir(root_path)
+        .env(
+            "GIT_CONFIG_PARAMETERS",
+            "'blame.ignoreRevsFile'='.git-blame-ignore-revs' \
+             'dive.syntaxesDir'='/opt/syntaxes' \
+             'dive.syntaxMap'='*.j2:Jinja2' 'dive.syntaxMap'='ci/Jenkinsfile:Groovy'",
+        )
+        .env_remove("COLORFGBG")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+[color]
+	ui = auto  # default
+[core]
+	pager = [..]
+[blame]
+	ignoreRevsFile = .git-blame-ignore-revs  # git-cli
+	markIgnoredLines = false  # default
+	markUnblamableLines = false  # default
+[dive]
+	theme = auto  # default
+	colorDepth = auto  # default
+	gutter = origin  # default
+	colorBy = none  # default
+	originStyle = relative  # default
+	pullRequestPattern =   # unset
+	wrap = wrap  # default
+	firstParent = true  # default
+	ignoreWhitespace = true  # default
+	detectMoves = true  # default
+	detectCopies = 3  # default
+	interactive = false  # default
+	syntaxesDir = /opt/syntaxes  # git-cli
+	themesDir =   # unset
+	syntaxMap = *.j2:Jinja2, ci/Jenkinsfile:Groovy  # git-cli
+[dive \"theme\"]
+	light = Monokai Extended Light  # default
+	dark = Monokai Extended  # default
+# Using theme `Monokai Extended`: assuming a dark background
+",
+        );
+
+    root.close().unwrap();
+}
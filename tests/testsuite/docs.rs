@@ -31,6 +31,7 @@ fn screenshot() {
             .with_alias("git-dive", &cmd_path.to_string_lossy())
             .with_current_dir(&repo_path)
             .with_env("CLICOLOR_FORCE", "1")
+            .with_env("COLORTERM", "truecolor")
             // Make it independent of the tester's user config
             .with_env("GIT_CONFIG_PARAMETERS", "'dive.theme'='Monokai Extended'"),
     )
@@ -0,0 +1,457 @@
+#[test]
+fn json() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun();\nstop();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B\n\nAdd stop".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--format=json")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            r#"{
+  "path": "basic.js",
+  "rev": "HEAD",
+  "origin_base": "HEAD",
+  "hunks": [
+    {
+      "commit": "[..]",
+      "short": "[..]",
+      "origin": "HEAD~1",
+      "author": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "committer": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "summary": "A",
+      "orig_path": "basic.js",
+      "orig_start_line": 1,
+      "final_start_line": 1,
+      "ignored": false,
+      "unblamable": false,
+      "lines": [
+        "init();",
+        "run();"
+      ]
+    },
+    {
+      "commit": "[..]",
+      "short": "[..]",
+      "origin": "HEAD",
+      "author": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "committer": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "summary": "B",
+      "orig_path": "basic.js",
+      "orig_start_line": 3,
+      "final_start_line": 3,
+      "ignored": false,
+      "unblamable": false,
+      "lines": [
+        "stop();"
+      ]
+    }
+  ]
+}
+"#,
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn json_rev() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    // Lines are read from `OLD`, with origins relative to `NEW`
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--format=json")
+        .arg("--reverse=HEAD~1..HEAD")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            r#"{
+  "path": "basic.js",
+  "rev": "HEAD~1",
+  "origin_base": "HEAD",
+  "hunks": [
+    {
+      "commit": "[..]",
+      "short": "[..]",
+      "origin": "HEAD",
+      "author": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "committer": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "summary": "B",
+      "orig_path": "basic.js",
+      "orig_start_line": 1,
+      "final_start_line": 1,
+      "ignored": false,
+      "unblamable": false,
+      "lines": [
+        "init();"
+      ]
+    },
+    {
+      "commit": "[..]",
+      "short": "[..]",
+      "origin": "HEAD~1",
+      "author": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "committer": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "summary": "A",
+      "orig_path": "basic.js",
+      "orig_start_line": 2,
+      "final_start_line": 2,
+      "ignored": false,
+      "unblamable": false,
+      "lines": [
+        "run();"
+      ]
+    }
+  ]
+}
+"#,
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    std::fs::write(root_path.join("basic.js"), "init();\nstop();\n").unwrap();
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--format=json")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            r#"{
+  "path": "basic.js",
+  "rev": "WORKTREE",
+  "origin_base": "HEAD",
+  "hunks": [
+    {
+      "commit": "[..]",
+      "short": "[..]",
+      "origin": "HEAD~1",
+      "author": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "committer": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "summary": "A",
+      "orig_path": "basic.js",
+      "orig_start_line": 1,
+      "final_start_line": 1,
+      "ignored": false,
+      "unblamable": false,
+      "lines": [
+        "init();"
+      ]
+    },
+    {
+      "commit": "0000000000000000000000000000000000000000",
+      "short": "0000000",
+      "origin": "WORKTREE",
+      "author": {
+        "name": "Not Committed Yet",
+        "email": "not.committed.yet",
+        "time": [..],
+        "offset": [..]
+      },
+      "committer": {
+        "name": "Not Committed Yet",
+        "email": "not.committed.yet",
+        "time": [..],
+        "offset": [..]
+      },
+      "summary": "Version of file from working tree",
+      "orig_path": "basic.js",
+      "orig_start_line": 2,
+      "final_start_line": 2,
+      "ignored": false,
+      "unblamable": false,
+      "lines": [
+        "stop();"
+      ]
+    }
+  ]
+}
+"#,
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn porcelain() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "init();\nrun();\nstop();\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--porcelain")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+[..] 1 1 2
+author [..]
+author-mail <[..]>
+author-time [..]
+author-tz [..]
+committer [..]
+committer-mail <[..]>
+committer-time [..]
+committer-tz [..]
+summary A
+dive-origin HEAD~1
+boundary
+filename basic.js
+	init();
+[..] 2 2
+	run();
+[..] 3 3 1
+author [..]
+author-mail <[..]>
+author-time [..]
+author-tz [..]
+committer [..]
+committer-mail <[..]>
+committer-time [..]
+committer-tz [..]
+summary B
+dive-origin HEAD
+previous [..] basic.js
+filename basic.js
+	stop();
+",
+        )
+        .stderr_eq(
+            "\
+",
+        );
+
+    root.close().unwrap();
+}
+
+#[test]
+fn json_spans() {
+    let root = snapbox::dir::DirRoot::mutable_temp().unwrap();
+    let root_path = root.path().unwrap();
+    let plan = git_fixture::TodoList {
+        commands: vec![
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "run(1);\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("A".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Tree(git_fixture::Tree {
+                files: [("basic.js", "run(2);\n")]
+                    .into_iter()
+                    .map(|(p, c)| (p.into(), c.into()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+                message: Some("B".to_owned()),
+                author: None,
+            }),
+            git_fixture::Command::Branch("main".into()),
+        ],
+        ..Default::default()
+    };
+    plan.run(root_path).unwrap();
+
+    snapbox::cmd::Command::new(snapbox::cmd::cargo_bin!("git-dive"))
+        .arg("--format=json")
+        .arg("--granularity=word")
+        .arg("basic.js")
+        .current_dir(root_path)
+        .assert()
+        .success()
+        .stdout_eq(
+            r#"{
+  "path": "basic.js",
+  "rev": "HEAD",
+  "origin_base": "HEAD",
+  "hunks": [
+    {
+      "commit": "[..]",
+      "short": "[..]",
+      "origin": "HEAD",
+      "author": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "committer": {
+        "name": "[..]",
+        "email": "[..]",
+        "time": [..],
+        "offset": [..]
+      },
+      "summary": "B",
+      "orig_path": "basic.js",
+      "orig_start_line": 1,
+      "final_start_line": 1,
+      "ignored": false,
+      "unblamable": false,
+      "lines": [
+        "run(2);"
+      ],
+      "spans": [
+        [
+          {
+            "start": 0,
+            "end": 4,
+            "commit": "[..]",
+            "origin": "HEAD~1"
+          },
+          {
+            "start": 4,
+            "end": 5,
+            "commit": "[..]",
+            "origin": "HEAD"
+          },
+          {
+            "start": 5,
+            "end": 7,
+            "commit": "[..]",
+            "origin": "HEAD~1"
+          }
+        ]
+      ]
+    }
+  ]
+}
+"#,
+        );
+
+    root.close().unwrap();
+}